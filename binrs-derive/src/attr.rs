@@ -0,0 +1,232 @@
+use syn::meta::ParseNestedMeta;
+
+const CODECS: [&str; 8] = ["none_is_empty", "tag_before_len", "nullable_ptr", "varint", "fixed", "rest", "cstr", "with"];
+
+fn unknown_attribute(meta: &ParseNestedMeta) -> syn::Error {
+    let path = meta.path.get_ident().map_or_else(|| "?".to_string(), |ident| ident.to_string());
+    meta.error(format!("unknown bin attribute `{path}`"))
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub skip_encode: bool,
+    pub skip_decode: bool,
+    pub none_is_empty: bool,
+    pub len: Option<syn::Ident>,
+    pub pre: Option<syn::ExprPath>,
+    pub post: Option<syn::ExprPath>,
+    pub len_before_tag: bool,
+    pub nullable_ptr: bool,
+    pub varint: bool,
+    pub cstr: bool,
+    pub rest: bool,
+    pub fixed: Option<usize>,
+    pub with: Option<syn::ExprPath>,
+    pub default: bool,
+    pub default_expr: Option<syn::Expr>,
+    pub since: Option<u16>,
+    pub at: Option<usize>,
+    pub epoch: Option<syn::Ident>,
+    pub endian: Option<syn::Ident>,
+    pub compute: Option<syn::ExprPath>,
+    pub skip_if: Option<syn::ExprPath>,
+}
+
+impl FieldAttrs {
+    pub fn custom_codec(&self) -> bool {
+        self.none_is_empty || self.len_before_tag || self.nullable_ptr || self.varint || self.cstr || self.rest || self.with.is_some()
+    }
+
+    pub fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        let mut codec: Option<syn::Path> = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("bin")) {
+            attr.parse_nested_meta(|meta| {
+                if CODECS.iter().any(|name| meta.path.is_ident(name))
+                    && let Some(first) = codec.replace(meta.path.clone())
+                {
+                    let first = first.get_ident().map(ToString::to_string).unwrap_or_default();
+                    return Err(meta.error(format!("conflicting codec attribute, field already uses `{first}`")));
+                }
+                if meta.path.is_ident("skip") {
+                    parsed.skip_encode = true;
+                    parsed.skip_decode = true;
+                } else if meta.path.is_ident("skip_encode") {
+                    parsed.skip_encode = true;
+                } else if meta.path.is_ident("skip_decode") {
+                    parsed.skip_decode = true;
+                } else if meta.path.is_ident("none_is_empty") {
+                    parsed.none_is_empty = true;
+                } else if meta.path.is_ident("varint") {
+                    parsed.varint = true;
+                } else if meta.path.is_ident("rest") {
+                    parsed.rest = true;
+                } else if meta.path.is_ident("cstr") {
+                    parsed.cstr = true;
+                } else if meta.path.is_ident("nullable_ptr") {
+                    parsed.nullable_ptr = true;
+                } else if meta.path.is_ident("len") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let variant = match lit.value().as_str() {
+                        "u8" => "U8",
+                        "u16" => "U16",
+                        "u32" => "U32",
+                        "u64" => "U64",
+                        "varint" => "Varint",
+                        "i32" => "I32",
+                        "i64" => "I64",
+                        _ => return Err(syn::Error::new(lit.span(), "unsupported length prefix")),
+                    };
+                    parsed.len = Some(syn::Ident::new(variant, lit.span()));
+                } else if meta.path.is_ident("tag_before_len") {
+                    let tag_before_len = if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<syn::LitBool>()?.value
+                    } else {
+                        true
+                    };
+                    parsed.len_before_tag = !tag_before_len;
+                } else if meta.path.is_ident("endian") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let variant = match lit.value().as_str() {
+                        "little" => "Little",
+                        "big" => "Big",
+                        "native" => "Native",
+                        _ => return Err(syn::Error::new(lit.span(), "unsupported endianness")),
+                    };
+                    parsed.endian = Some(syn::Ident::new(variant, lit.span()));
+                } else if meta.path.is_ident("epoch") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let variant = match lit.value().as_str() {
+                        "unix" => "Unix",
+                        "windows" => "Windows",
+                        _ => return Err(syn::Error::new(lit.span(), "unsupported epoch")),
+                    };
+                    parsed.epoch = Some(syn::Ident::new(variant, lit.span()));
+                } else if meta.path.is_ident("fixed") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    parsed.fixed = Some(lit.base10_parse()?);
+                } else if meta.path.is_ident("at") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    parsed.at = Some(lit.base10_parse()?);
+                } else if meta.path.is_ident("with") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.with = Some(lit.parse()?);
+                } else if meta.path.is_ident("since") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    parsed.since = Some(lit.base10_parse()?);
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                    if meta.input.peek(syn::Token![=]) {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        parsed.default_expr = Some(lit.parse()?);
+                    }
+                } else if meta.path.is_ident("pre") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.pre = Some(lit.parse()?);
+                } else if meta.path.is_ident("post") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.post = Some(lit.parse()?);
+                } else if meta.path.is_ident("skip_if") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.skip_if = Some(lit.parse()?);
+                } else if meta.path.is_ident("compute") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.compute = Some(lit.parse()?);
+                } else {
+                    return Err(unknown_attribute(&meta));
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(compute) = &parsed.compute
+            && !parsed.skip_decode
+        {
+            return Err(syn::Error::new_spanned(compute, "compute requires skip or skip_decode"));
+        }
+        if let Some(skip_if) = &parsed.skip_if
+            && (!parsed.default || parsed.skip_decode)
+        {
+            return Err(syn::Error::new_spanned(skip_if, "skip_if requires default and cannot be combined with skip_decode"));
+        }
+        Ok(parsed)
+    }
+}
+
+#[derive(Default, PartialEq)]
+pub enum Tag {
+    #[default]
+    Index,
+    String,
+}
+
+pub struct Magic {
+    pub ty: syn::Type,
+    pub value: syn::Expr,
+}
+
+impl Magic {
+    fn parse(lit: syn::Lit) -> syn::Result<Self> {
+        match lit {
+            syn::Lit::ByteStr(bytes) => {
+                let len = bytes.value().len();
+                Ok(Self { ty: syn::parse_quote! { [u8; #len] }, value: syn::parse_quote! { *#bytes } })
+            }
+            syn::Lit::Int(int) if !int.suffix().is_empty() => {
+                let ty = syn::Ident::new(int.suffix(), int.span());
+                Ok(Self { ty: syn::parse_quote! { #ty }, value: syn::parse_quote! { #int } })
+            }
+            lit => Err(syn::Error::new(lit.span(), "magic must be a byte string or a suffixed integer")),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub tag: Tag,
+    pub other: Option<syn::Ident>,
+    pub sized_variants: bool,
+    pub endian_field: Option<syn::Ident>,
+    pub magic: Option<Magic>,
+    pub bound: Option<syn::punctuated::Punctuated<syn::WherePredicate, syn::Token![,]>>,
+}
+
+impl ContainerAttrs {
+    pub fn framed(&self) -> bool {
+        self.sized_variants || self.other.is_some()
+    }
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("bin")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.tag = match lit.value().as_str() {
+                        "index" => Tag::Index,
+                        "string" => Tag::String,
+                        _ => return Err(syn::Error::new(lit.span(), "unsupported enum tag")),
+                    };
+                } else if meta.path.is_ident("endian_field") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.endian_field = Some(lit.parse()?);
+                } else if meta.path.is_ident("bound") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.bound = Some(lit.parse_with(syn::punctuated::Punctuated::parse_terminated)?);
+                } else if meta.path.is_ident("magic") {
+                    parsed.magic = Some(Magic::parse(meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("sized_variants") {
+                    parsed.sized_variants = true;
+                } else if meta.path.is_ident("other") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    parsed.other = Some(lit.parse()?);
+                } else {
+                    return Err(unknown_attribute(&meta));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,265 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{attr::Tag, construct, endian_split, has_offsets, Data, Field, Input};
+
+fn decode_field(field: &Field) -> TokenStream {
+    let ty = field.ty;
+    let decode = if field.attrs.none_is_empty {
+        quote! { binrs::private::decode_none_is_empty(decoder) }
+    } else if field.attrs.len_before_tag {
+        quote! { binrs::private::decode_len_before_tag(decoder) }
+    } else if field.attrs.nullable_ptr {
+        quote! { binrs::private::decode_nullable_ptr(decoder) }
+    } else if field.attrs.varint {
+        quote! { <#ty as binrs::varint::VarInt>::decode_varint(decoder) }
+    } else if let Some(fixed) = field.attrs.fixed {
+        quote! { binrs::decoder::Decoder::decode_string_padded(decoder, #fixed) }
+    } else if field.attrs.rest {
+        quote! { binrs::decoder::Decoder::decode_remaining(decoder).map(<#ty>::from) }
+    } else if field.attrs.cstr {
+        quote! { binrs::decoder::Decoder::decode_cstr(decoder) }
+    } else if let Some(with) = &field.attrs.with {
+        quote! { #with::decode(decoder) }
+    } else {
+        quote! { <#ty as binrs::decoder::Decode>::decode(decoder) }
+    };
+
+    let overrides = field.context_overrides();
+    let decode = if overrides.is_empty() {
+        decode
+    } else {
+        quote! {
+            binrs::private::decode_with_context(decoder, binrs::decoder::Decoder::context(decoder)#(#overrides)*, |decoder| #decode)
+        }
+    };
+
+    match &field.attrs.post {
+        Some(post) => quote! { #decode.map(#post) },
+        None => decode,
+    }
+}
+
+fn decode_fields(fields: &[Field], endian_field: Option<&syn::Ident>) -> TokenStream {
+    let decodes: Vec<_> = fields.iter().map(|field| {
+        let local = &field.local;
+        let default = field.default_value();
+        if field.attrs.skip_decode {
+            return quote! { let #local = #default; };
+        }
+        let label = field.label();
+        let decode = decode_field(field);
+        let skip = field.attrs.at.map(|at| quote! { binrs::private::skip_to(decoder, __binrs_start, #at)?; });
+        let decode = quote! {
+            {
+                #skip
+                binrs::decoder::Decoder::begin_field(decoder, #label);
+                let value = #decode?;
+                binrs::decoder::Decoder::end_field(decoder, #label);
+                value
+            }
+        };
+        let decode = if field.attrs.default {
+            quote! { if binrs::private::at_end(decoder, __binrs_end)? { #default } else #decode }
+        } else {
+            decode
+        };
+        let decode = match field.attrs.since {
+            Some(since) => quote! {
+                if binrs::decoder::Decoder::context(decoder).has_version(#since) { #decode } else { #default }
+            },
+            None => decode,
+        };
+        quote! { let #local = #decode; }
+    }).collect();
+    let start = has_offsets(fields).then(|| quote! { let __binrs_start = binrs::decoder::Decoder::position(decoder); });
+    let Some((marker, split)) = endian_split(fields, endian_field) else {
+        return quote! { #start #(#decodes)* };
+    };
+    let (head, tail) = decodes.split_at(split);
+    let marker = &marker.local;
+    let locals: Vec<_> = fields[split..].iter().map(|f| &f.local).collect();
+    quote! {
+        #start
+        #(#head)*
+        let (#(#locals,)*) = binrs::private::decode_with_context(
+            decoder,
+            binrs::decoder::Decoder::context(decoder).with_endian(binrs::endian::EndianMarker::endian(&#marker)?),
+            |decoder| {
+                #(#tail)*
+                Ok((#(#locals,)*))
+            },
+        )?;
+    }
+}
+
+fn has_defaults(fields: &[Field]) -> bool {
+    fields.iter().any(|f| f.attrs.default && !f.attrs.skip_decode)
+}
+
+fn min_size(fields: &[Field]) -> TokenStream {
+    let sizes = fields.iter().filter(|f| !f.attrs.skip_decode && !f.attrs.default && f.attrs.since.is_none() && !f.attrs.custom_codec()).map(|f| {
+        let ty = f.ty;
+        match f.attrs.fixed {
+            Some(fixed) => quote! { + #fixed },
+            None => quote! { + <#ty as binrs::decoder::Decode>::MIN_SIZE },
+        }
+    });
+    quote! { 0 #(#sizes)* }
+}
+
+fn fixed_size(fields: &[Field]) -> TokenStream {
+    if has_offsets(fields) {
+        return quote! { None };
+    }
+    let sizes = fields.iter().filter(|f| !f.attrs.skip_decode).map(|f| {
+        let ty = f.ty;
+        if f.attrs.custom_codec() || f.attrs.since.is_some() || f.attrs.skip_if.is_some() {
+            quote! { None }
+        } else if let Some(fixed) = f.attrs.fixed {
+            quote! { Some(#fixed) }
+        } else {
+            quote! { <#ty as binrs::decoder::Decode>::FIXED_SIZE }
+        }
+    });
+    quote! { binrs::private::sum_fixed_sizes(&[#(#sizes),*]) }
+}
+
+pub fn derive(input: &Input) -> TokenStream {
+    let name = input.ident;
+
+    let trailing_defaults = match &input.data {
+        Data::Struct(_, fields) => has_defaults(fields),
+        Data::Enum(variants) => !input.attrs.framed() && variants.iter().any(|v| has_defaults(&v.fields)),
+    };
+
+    let (min_size, fixed_size, body) = match &input.data {
+        Data::Struct(style, fields) => {
+            let decodes = decode_fields(fields, input.attrs.endian_field.as_ref());
+            let value = construct(quote! { Self }, *style, fields);
+            let computes: Vec<_> = fields.iter().filter_map(|f| {
+                let member = &f.member;
+                let compute = f.attrs.compute.as_ref()?;
+                Some(quote! { __binrs_value.#member = #compute(&__binrs_value); })
+            }).collect();
+            let body = if computes.is_empty() {
+                quote! { Ok(#value) }
+            } else {
+                quote! {
+                    let mut __binrs_value = #value;
+                    #(#computes)*
+                    Ok(__binrs_value)
+                }
+            };
+            (min_size(fields), fixed_size(fields), quote! {
+                #decodes
+                #body
+            })
+        }
+        Data::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let tag = match input.attrs.tag {
+                    Tag::Index => {
+                        let index = variant.index;
+                        quote! { #index }
+                    }
+                    Tag::String => {
+                        let name = ident.to_string();
+                        quote! { #name }
+                    }
+                };
+                let decodes = decode_fields(&variant.fields, None);
+                let value = construct(quote! { Self::#ident }, variant.style, &variant.fields);
+                if input.attrs.framed() {
+                    let end = has_defaults(&variant.fields).then(|| quote! { let __binrs_end = binrs::private::frame_end(decoder); });
+                    quote! {
+                        #tag => binrs::private::decode_framed(decoder, |decoder| {
+                            #end
+                            #decodes
+                            Ok(#value)
+                        }),
+                    }
+                } else {
+                    quote! {
+                        #tag => {
+                            #decodes
+                            Ok(#value)
+                        }
+                    }
+                }
+            });
+            let (unknown, invalid) = match input.attrs.tag {
+                Tag::Index => (quote! { __binrs_tag }, quote! { binrs::error::Error::at(__binrs_tag_start, binrs::error::Error::InvalidEnumVariant(__binrs_tag)) }),
+                Tag::String => (quote! { _ }, quote! { binrs::error::Error::at(__binrs_tag_start, binrs::error::Error::InvalidTag) }),
+            };
+            let fallback = match &input.attrs.other {
+                Some(other) => quote! {
+                    _ => {
+                        binrs::private::skip_framed(decoder)?;
+                        Ok(Self::#other)
+                    }
+                },
+                None if input.attrs.framed() => quote! {
+                    #unknown => {
+                        binrs::private::skip_framed(decoder)?;
+                        Err(#invalid)
+                    }
+                },
+                None => quote! { #unknown => Err(#invalid), },
+            };
+            let (min_size, tag) = match input.attrs.tag {
+                Tag::Index => (quote! { 4 }, quote! { binrs::decoder::Decoder::decode_u32(decoder)? }),
+                Tag::String => (quote! { 1 }, quote! { binrs::decoder::Decoder::decode_string(decoder)?.as_str() }),
+            };
+            (min_size, quote! { None }, quote! {
+                let __binrs_tag_start = binrs::decoder::Decoder::position(decoder);
+                match #tag {
+                    #(#arms)*
+                    #fallback
+                }
+            })
+        }
+    };
+
+    let (min_size, fixed_size, body) = match &input.attrs.magic {
+        Some(magic) => {
+            let (ty, value) = (&magic.ty, &magic.value);
+            (
+                quote! { <#ty as binrs::decoder::Decode>::MIN_SIZE + #min_size },
+                quote! { binrs::private::sum_fixed_sizes(&[<#ty as binrs::decoder::Decode>::FIXED_SIZE, #fixed_size]) },
+                quote! {
+                    binrs::private::decode_magic::<#ty, _>(decoder, &#value)?;
+                    #body
+                },
+            )
+        }
+        None => (min_size, fixed_size, body),
+    };
+
+    let body = if trailing_defaults {
+        quote! {
+            let __binrs_end = binrs::private::take_value_end(decoder);
+            #body
+        }
+    } else {
+        body
+    };
+
+    let generics = input.bounded_generics(quote! { binrs::decoder::Decode }, |attrs| attrs.skip_decode);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics binrs::decoder::Decode for #name #ty_generics #where_clause {
+            const MIN_SIZE: usize = #min_size;
+            const FIXED_SIZE: Option<usize> = #fixed_size;
+            const TRAILING_DEFAULTS: bool = #trailing_defaults;
+
+            fn decode<__D: binrs::decoder::Decoder>(decoder: &mut __D) -> Result<Self, binrs::error::Error> {
+                binrs::private::decode_nested(decoder, |decoder| {
+                    #body
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{attr::Tag, endian_split, has_offsets, Data, Field, Input, Style};
+
+fn encode_field(field: &Field, value: TokenStream) -> TokenStream {
+    let value = match &field.attrs.pre {
+        Some(pre) => quote! { &#pre(#value) },
+        None => value,
+    };
+
+    let encode = if field.attrs.none_is_empty {
+        quote! { binrs::private::encode_none_is_empty(#value, encoder) }
+    } else if field.attrs.len_before_tag {
+        quote! { binrs::private::encode_len_before_tag(#value, encoder) }
+    } else if field.attrs.nullable_ptr {
+        quote! { binrs::private::encode_nullable_ptr(#value, encoder) }
+    } else if field.attrs.varint {
+        quote! { binrs::varint::VarInt::encode_varint(#value, encoder) }
+    } else if let Some(fixed) = field.attrs.fixed {
+        quote! { binrs::encoder::Encoder::encode_string_padded(encoder, #value, #fixed) }
+    } else if field.attrs.rest {
+        quote! { binrs::encoder::Encoder::encode_bytes(encoder, #value) }
+    } else if field.attrs.cstr {
+        quote! { binrs::encoder::Encoder::encode_cstr(encoder, #value) }
+    } else if let Some(with) = &field.attrs.with {
+        quote! { #with::encode(#value, encoder) }
+    } else {
+        quote! { binrs::encoder::Encode::encode(#value, encoder) }
+    };
+
+    let overrides = field.context_overrides();
+    if overrides.is_empty() {
+        return encode;
+    }
+    quote! {
+        binrs::private::encode_with_context(encoder, binrs::encoder::Encoder::context(encoder)#(#overrides)*, |encoder| #encode)
+    }
+}
+
+fn encode_fields<'a>(fields: &'a [Field], endian_field: Option<&syn::Ident>, value: impl Fn(&'a Field) -> TokenStream) -> TokenStream {
+    let encodes: Vec<_> = fields.iter().map(|field| {
+        if field.attrs.skip_encode {
+            return TokenStream::new();
+        }
+        let label = field.label();
+        let encode = encode_field(field, value(field));
+        let padding = field.attrs.at.map(|at| quote! { binrs::private::encode_padding_to(encoder, __binrs_start, #at)?; });
+        let encode = quote! {
+            #padding
+            binrs::encoder::Encoder::begin_field(encoder, #label);
+            #encode?;
+            binrs::encoder::Encoder::end_field(encoder, #label);
+        };
+        let encode = match &field.attrs.skip_if {
+            Some(skip_if) => {
+                let value = value(field);
+                quote! {
+                    if !#skip_if(#value) {
+                        #encode
+                    }
+                }
+            }
+            None => encode,
+        };
+        match field.attrs.since {
+            Some(since) => quote! {
+                if binrs::encoder::Encoder::context(encoder).has_version(#since) {
+                    #encode
+                }
+            },
+            None => encode,
+        }
+    }).collect();
+    let start = has_offsets(fields).then(|| quote! { let __binrs_start = binrs::encoder::Encoder::position(encoder); });
+    let Some((marker, split)) = endian_split(fields, endian_field) else {
+        return quote! { #start #(#encodes)* };
+    };
+    let (head, tail) = encodes.split_at(split);
+    let marker = value(marker);
+    quote! {
+        #start
+        #(#head)*
+        binrs::private::encode_with_context(
+            encoder,
+            binrs::encoder::Encoder::context(encoder).with_endian(binrs::endian::EndianMarker::endian(#marker)?),
+            |encoder| {
+                #(#tail)*
+                Ok(())
+            },
+        )?;
+    }
+}
+
+fn pattern(path: TokenStream, style: Style, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|f| &f.member);
+    let bindings: Vec<_> = fields.iter()
+        .map(|f| if f.attrs.skip_encode { quote! { _ } } else { let local = &f.local; quote! { #local } })
+        .collect();
+    match style {
+        Style::Named => quote! { #path { #(#members: #bindings),* } },
+        Style::Unnamed => quote! { #path ( #(#bindings),* ) },
+        Style::Unit => path,
+    }
+}
+
+pub fn derive(input: &Input) -> TokenStream {
+    let name = input.ident;
+
+    let body = match &input.data {
+        Data::Struct(_, fields) => encode_fields(fields, input.attrs.endian_field.as_ref(), |f| {
+            let member = &f.member;
+            quote! { &self.#member }
+        }),
+        Data::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let ident = variant.ident;
+                let pattern = pattern(quote! { Self::#ident }, variant.style, &variant.fields);
+                let tag = match input.attrs.tag {
+                    Tag::Index => {
+                        let index = variant.index;
+                        quote! { binrs::encoder::Encoder::encode_u32(encoder, #index)?; }
+                    }
+                    Tag::String => {
+                        let name = ident.to_string();
+                        quote! { binrs::encoder::Encoder::encode_string(encoder, #name)?; }
+                    }
+                };
+                let fields = encode_fields(&variant.fields, None, |f| {
+                    let local = &f.local;
+                    quote! { #local }
+                });
+                let fields = if input.attrs.framed() {
+                    quote! { binrs::private::encode_framed(encoder, |encoder| { #fields Ok(()) })?; }
+                } else {
+                    fields
+                };
+                quote! {
+                    #pattern => {
+                        #tag
+                        #fields
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    };
+
+    let magic = input.attrs.magic.as_ref().map(|magic| {
+        let (ty, value) = (&magic.ty, &magic.value);
+        quote! { <#ty as binrs::encoder::Encode>::encode(&#value, encoder)?; }
+    });
+
+    let generics = input.bounded_generics(quote! { binrs::encoder::Encode }, |attrs| attrs.skip_encode);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics binrs::encoder::Encode for #name #ty_generics #where_clause {
+            fn encode<__E: binrs::encoder::Encoder>(&self, encoder: &mut __E) -> Result<(), binrs::error::Error> {
+                #magic
+                #body
+                Ok(())
+            }
+        }
+    }
+}
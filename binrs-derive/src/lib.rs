@@ -1,84 +1,357 @@
-use quote::quote;
+mod attr;
+mod decode;
+mod encode;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(Encode, attributes(bin))]
-pub fn encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
+use attr::{ContainerAttrs, FieldAttrs};
 
-    let name = &input.ident;
+struct Field<'a> {
+    member: syn::Member,
+    ty: &'a syn::Type,
+    local: syn::Ident,
+    attrs: FieldAttrs,
+}
 
-    let fields = match input.data {
-        syn::Data::Struct(data_struct) => match data_struct.fields {
-            syn::Fields::Named(fields_named) => fields_named.named,
-            _ => return syn::Error::new(name.span(), "This macro works only for structs with named fields").to_compile_error().into()
-        },
-        _ => return syn::Error::new(name.span(), "This macro works only for structs").to_compile_error().into()
-    };
+impl Field<'_> {
+    fn is_named(&self, name: &syn::Ident) -> bool {
+        matches!(&self.member, syn::Member::Named(ident) if ident == name)
+    }
+
+    fn label(&self) -> String {
+        match &self.member {
+            syn::Member::Named(ident) => ident.to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        }
+    }
+
+    fn default_value(&self) -> TokenStream {
+        match &self.attrs.default_expr {
+            Some(expr) => quote! { #expr },
+            None => quote! { Default::default() },
+        }
+    }
+
+    fn context_overrides(&self) -> Vec<TokenStream> {
+        let mut overrides = Vec::new();
+        if let Some(len) = &self.attrs.len {
+            overrides.push(quote! { .with_outer_len(binrs::context::LenPrefix::#len) });
+        }
+        if let Some(endian) = &self.attrs.endian {
+            overrides.push(quote! { .with_endian(binrs::endian::Endianness::#endian) });
+        }
+        if let Some(epoch) = &self.attrs.epoch {
+            overrides.push(quote! { .with_epoch(binrs::context::Epoch::#epoch) });
+        }
+        overrides
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Style {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+struct Variant<'a> {
+    ident: &'a syn::Ident,
+    index: u32,
+    style: Style,
+    fields: Vec<Field<'a>>,
+}
+
+enum Data<'a> {
+    Struct(Style, Vec<Field<'a>>),
+    Enum(Vec<Variant<'a>>),
+}
+
+struct Input<'a> {
+    ident: &'a syn::Ident,
+    generics: &'a syn::Generics,
+    attrs: ContainerAttrs,
+    data: Data<'a>,
+}
+
+impl Input<'_> {
+    fn fields(&self) -> Vec<&Field<'_>> {
+        match &self.data {
+            Data::Struct(_, fields) => fields.iter().collect(),
+            Data::Enum(variants) => variants.iter().flat_map(|v| &v.fields).collect(),
+        }
+    }
 
-    let field_names = fields.iter()
-        .filter(|f| {
-            !f.attrs.iter().any(|attr| {
-                attr.path().is_ident("bin") &&
-                attr.parse_args::<syn::Ident>().map_or(false, |ident| ident == "skip")
-            })
-        })
-        .map(|f| &f.ident);
-
-    let generated = quote! {
-        impl binrs::encoder::Encode for #name {
-            fn encode<E: binrs::encoder::Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-                #(self.#field_names.encode(encoder)?;)*                
-                Ok(())
+    fn bounded_generics(&self, bound: TokenStream, skipped: fn(&FieldAttrs) -> bool) -> syn::Generics {
+        let mut generics = self.generics.clone();
+        let where_clause = generics.make_where_clause();
+        if let Some(predicates) = &self.attrs.bound {
+            where_clause.predicates.extend(predicates.iter().cloned());
+            return generics;
+        }
+        let fields = self.fields();
+        for param in self.generics.type_params() {
+            let ident = &param.ident;
+            let used = fields.iter()
+                .filter(|f| !skipped(&f.attrs) && !is_phantom(f.ty))
+                .any(|f| mentions(f.ty.to_token_stream(), ident));
+            if used {
+                where_clause.predicates.push(syn::parse_quote! { #ident: #bound });
             }
-        } 
+        }
+        generics
+    }
+}
+
+fn is_phantom(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "PhantomData"))
+}
+
+fn mentions(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(other) => other == *ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+fn parse_fields(fields: &syn::Fields) -> syn::Result<(Style, Vec<Field<'_>>)> {
+    let style = match fields {
+        syn::Fields::Named(_) => Style::Named,
+        syn::Fields::Unnamed(_) => Style::Unnamed,
+        syn::Fields::Unit => Style::Unit,
     };
 
-    generated.into()
+    let fields: Vec<_> = fields.iter()
+        .enumerate()
+        .map(|(i, f)| Ok(Field {
+            member: f.ident.clone().map_or_else(|| syn::Member::Unnamed(i.into()), syn::Member::Named),
+            ty: &f.ty,
+            local: format_ident!("__binrs_field_{}", i),
+            attrs: FieldAttrs::parse(&f.attrs)?,
+        }))
+        .collect::<syn::Result<_>>()?;
+    if let Some(field) = fields.iter().rev().skip(1).find(|f| f.attrs.rest) {
+        return Err(syn::Error::new_spanned(field.ty, "rest must be the last field"));
+    }
+    // A skipped value is only detectable as missing input, so nothing may be
+    // decoded after it.
+    if let Some(last) = fields.iter().rposition(|f| !f.attrs.skip_decode)
+        && let Some(field) = fields[..last].iter().find(|f| f.attrs.skip_if.is_some())
+    {
+        return Err(syn::Error::new_spanned(field.ty, "skip_if must be on the last decoded field"));
+    }
+
+    Ok((style, fields))
+}
+
+fn discriminant(expr: &syn::Expr) -> syn::Result<u32> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(lit), .. }) => lit.base10_parse(),
+        _ => Err(syn::Error::new_spanned(expr, "enum discriminant must be an integer literal")),
+    }
+}
+
+fn parse_input(input: &DeriveInput) -> syn::Result<Input<'_>> {
+    let data = match &input.data {
+        syn::Data::Struct(data_struct) => {
+            let (style, fields) = parse_fields(&data_struct.fields)?;
+            Data::Struct(style, fields)
+        }
+        syn::Data::Enum(data_enum) => {
+            let mut next = 0u32;
+            Data::Enum(data_enum.variants.iter()
+                .map(|v| {
+                    let (style, fields) = parse_fields(&v.fields)?;
+                    let index = match &v.discriminant {
+                        Some((_, expr)) => discriminant(expr)?,
+                        None => next,
+                    };
+                    next = index.wrapping_add(1);
+                    Ok(Variant { ident: &v.ident, index, style, fields })
+                })
+                .collect::<syn::Result<_>>()?)
+        }
+        syn::Data::Union(data_union) => return Err(syn::Error::new(data_union.union_token.span, "This macro works only for structs and enums"))
+    };
+
+    let attrs = ContainerAttrs::parse(&input.attrs)?;
+    if let Some(endian_field) = &attrs.endian_field {
+        let Data::Struct(Style::Named, fields) = &data else {
+            return Err(syn::Error::new(endian_field.span(), "endian_field is only supported on structs with named fields"));
+        };
+        match fields.iter().find(|f| f.is_named(endian_field)) {
+            Some(field) if field.attrs.skip_encode || field.attrs.skip_decode => return Err(syn::Error::new(endian_field.span(), "endian_field must not be skipped")),
+            Some(_) => {}
+            None => return Err(syn::Error::new(endian_field.span(), "endian_field must name a field of this struct")),
+        }
+    }
+    if let Data::Enum(variants) = &data
+        && let Some(compute) = variants.iter().flat_map(|v| &v.fields).find_map(|f| f.attrs.compute.as_ref())
+    {
+        return Err(syn::Error::new_spanned(compute, "compute is only supported on struct fields"));
+    }
+    if attrs.sized_variants && !matches!(data, Data::Enum(_)) {
+        return Err(syn::Error::new_spanned(&input.ident, "sized_variants is only supported on enums"));
+    }
+    if let Some(other) = &attrs.other {
+        let Data::Enum(variants) = &data else {
+            return Err(syn::Error::new(other.span(), "other is only supported on enums"));
+        };
+        match variants.iter().find(|v| v.ident == other) {
+            Some(Variant { style: Style::Unit, .. }) => {}
+            Some(_) => return Err(syn::Error::new(other.span(), "other variant must be a unit variant")),
+            None => return Err(syn::Error::new(other.span(), "other must name a variant of this enum")),
+        }
+    }
+
+    Ok(Input { ident: &input.ident, generics: &input.generics, attrs, data })
 }
 
-#[proc_macro_derive(Decode, attributes(binrs))]
+fn construct(path: TokenStream, style: Style, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|f| &f.member);
+    let locals = fields.iter().map(|f| &f.local);
+    match style {
+        Style::Named => quote! { #path { #(#members: #locals),* } },
+        Style::Unnamed => quote! { #path ( #(#locals),* ) },
+        Style::Unit => path,
+    }
+}
+
+fn endian_split<'a>(fields: &'a [Field<'a>], endian_field: Option<&syn::Ident>) -> Option<(&'a Field<'a>, usize)> {
+    let name = endian_field?;
+    fields.iter().position(|f| f.is_named(name)).map(|index| (&fields[index], index + 1))
+}
+
+fn has_offsets(fields: &[Field]) -> bool {
+    fields.iter().any(|f| !(f.attrs.skip_encode && f.attrs.skip_decode) && f.attrs.at.is_some())
+}
+
+#[proc_macro_derive(Encode, attributes(bin))]
+pub fn encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match parse_input(&input) {
+        Ok(input) => encode::derive(&input).into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+#[proc_macro_derive(Decode, attributes(bin))]
 pub fn decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
-    let name = &input.ident;
+    match parse_input(&input) {
+        Ok(input) => decode::derive(&input).into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, DeriveInput};
 
-    let fields = match input.data {
-        syn::Data::Struct(data_struct) => match data_struct.fields {
-            syn::Fields::Named(fields_named) => fields_named.named,
-            _ => return syn::Error::new(name.span(), "This macro works only for structs with named fields").to_compile_error().into()
-        },
-        _ => return syn::Error::new(name.span(), "This macro works only for structs").to_compile_error().into()
-    };
+    use super::parse_input;
 
-    let (field_names, field_types): (Vec<_>, Vec<_>) = fields.iter()
-        .filter(|f| {
-            !f.attrs.iter().any(|attr| {
-                attr.path().is_ident("bin") &&
-                attr.parse_args::<syn::Ident>().map_or(false, |ident| ident == "skip")
-            })
-        })
-        .map(|f| (&f.ident, &f.ty))
-        .unzip();
-    
-    let skipped_fields = fields.iter()
-        .filter(|f| {
-            f.attrs.iter().any(|attr| {
-                attr.path().is_ident("bin") &&
-                attr.parse_args::<syn::Ident>().map_or(false, |ident| ident == "skip")
-            })
-        })
-        .map(|f| &f.ident);
-
-    let generated = quote! {
-        impl binrs::decoder::Decode for #name {
-            fn decode<D: binrs::decoder::Decoder>(decoder: &mut D) -> Result<Self, Error> {
-                Ok(Self {
-                    #(#field_names: <#field_types as binrs::decoder::Decode>::decode(decoder)?,)*
-                    #(#skipped_fields: Default::default(),)*      
-                })
+    fn error(input: DeriveInput) -> String {
+        match parse_input(&input) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn rejects_unions() {
+        let input = parse_quote! {
+            union Bits {
+                int: u32,
+                float: f32,
             }
-        } 
-    };
+        };
+        assert_eq!(error(input), "This macro works only for structs and enums");
+    }
+
+    #[test]
+    fn rejects_unknown_attributes() {
+        let input = parse_quote! {
+            struct Packet {
+                #[bin(compress)]
+                payload: Vec<u8>,
+            }
+        };
+        assert_eq!(error(input), "unknown bin attribute `compress`");
 
-    generated.into()
+        let input = parse_quote! {
+            struct Packet(#[bin(compress)] Vec<u8>);
+        };
+        assert_eq!(error(input), "unknown bin attribute `compress`");
+
+        let input = parse_quote! {
+            #[bin(compress)]
+            enum Packet {
+                Empty,
+            }
+        };
+        assert_eq!(error(input), "unknown bin attribute `compress`");
+    }
+
+    #[test]
+    fn rejects_conflicting_codecs() {
+        let input = parse_quote! {
+            struct Packet {
+                #[bin(varint, with = "binrs::varint")]
+                length: u64,
+            }
+        };
+        assert_eq!(error(input), "conflicting codec attribute, field already uses `varint`");
+
+        let input = parse_quote! {
+            enum Packet {
+                Data(#[bin(cstr)] #[bin(rest)] String),
+            }
+        };
+        assert_eq!(error(input), "conflicting codec attribute, field already uses `cstr`");
+    }
+
+    #[test]
+    fn rejects_misplaced_skip_if() {
+        let input = parse_quote! {
+            struct Packet {
+                #[bin(skip_if = "is_zero", default)]
+                flags: u32,
+                id: u32,
+            }
+        };
+        assert_eq!(error(input), "skip_if must be on the last decoded field");
+
+        let input = parse_quote! {
+            enum Packet {
+                Data(#[bin(skip_if = "is_zero", default)] u32, u32),
+            }
+        };
+        assert_eq!(error(input), "skip_if must be on the last decoded field");
+
+        let input = parse_quote! {
+            struct Packet {
+                #[bin(skip_if = "is_zero")]
+                flags: u32,
+            }
+        };
+        assert_eq!(error(input), "skip_if requires default and cannot be combined with skip_decode");
+    }
+
+    #[test]
+    fn accepts_trailing_skip_if() {
+        let input = parse_quote! {
+            struct Packet {
+                id: u32,
+                #[bin(skip_if = "is_zero", default)]
+                flags: u32,
+                #[bin(skip)]
+                cache: u32,
+            }
+        };
+        assert!(parse_input(&input).is_ok());
+    }
 }
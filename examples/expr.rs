@@ -0,0 +1,25 @@
+use binrs::prelude::*;
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+enum Expr {
+    Lit(i64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+}
+
+fn main() {
+    let expr = Expr::Add(
+        Box::new(Expr::Lit(1)),
+        Box::new(Expr::Neg(Box::new(Expr::Lit(2)))),
+    );
+    let bytes = expr.encode_to_bytes().unwrap();
+    println!("{bytes:x?}");
+    assert_eq!(Expr::decode_from_bytes(&bytes).unwrap(), expr);
+
+    let deep = (0..64).fold(Expr::Lit(0), |expr, _| Expr::Neg(Box::new(expr)));
+    let bytes = deep.encode_to_bytes().unwrap();
+    let ctx = Context::new(Endianness::Little).with_max_depth(16);
+    let err = Expr::decode_with_ctx(&bytes, ctx).unwrap_err();
+    assert!(matches!(err.kind(), Error::DepthLimitExceeded));
+    println!("{err}");
+}
@@ -1,11 +1,6 @@
 use std::collections::{BTreeSet, HashMap};
 
-use binrs::{
-    decoder::{Decode},
-    encoder::{Encode},
-    error::Error,
-};
-use binrs_derive::{Decode, Encode};
+use binrs::prelude::*;
 
 fn main() {
     let user = User {
@@ -52,4 +47,3 @@ pub struct User {
     #[bin(skip)]
     pub ignored: String,
 }
-
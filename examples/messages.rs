@@ -0,0 +1,34 @@
+use binrs::prelude::*;
+
+fn main() {
+    let messages = vec![
+        Message::Ping,
+        Message::Join(7, "lobby".to_string()),
+        Message::Chat {
+            room: 7,
+            text: "hello".to_string(),
+        },
+        Message::Leave(7, Status::Kicked),
+    ];
+
+    let bytes = messages.encode_to_bytes().unwrap();
+    println!("{bytes:x?}");
+
+    let messages = Vec::<Message>::decode_from_bytes(&bytes).unwrap();
+    println!("{messages:#?}");
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum Message {
+    Ping,
+    Join(u32, String),
+    Chat { room: u32, text: String },
+    Leave(u32, Status),
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum Status {
+    Normal = 1,
+    Kicked = 10,
+    Banned,
+}
@@ -0,0 +1,116 @@
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    Standard,
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"
+            }
+            Alphabet::UrlSafe => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+            }
+        }
+    }
+
+    fn value(self, c: u8) -> Result<u32, Error> {
+        self.symbols()
+            .iter()
+            .position(|s| *s == c)
+            .map(|v| v as u32)
+            .ok_or_else(|| "Invalid base64 character".into())
+    }
+}
+
+pub fn to_base64(bytes: &[u8], alphabet: Alphabet) -> String {
+    let symbols = alphabet.symbols();
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(symbols[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub fn from_base64(text: &str, alphabet: Alphabet) -> Result<Vec<u8>, Error> {
+    let data = text.trim_end_matches('=').as_bytes();
+    if !text.len().is_multiple_of(4) || text.len() - data.len() > 2 {
+        return Err("Invalid base64 padding".into());
+    }
+    if data.len() % 4 == 1 {
+        return Err("Invalid base64 length".into());
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut n = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= alphabet.value(*c)? << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        // Bits past the last decoded byte must be zero in canonical input.
+        if bytes[chunk.len()..].iter().any(|b| *b != 0) {
+            return Err("Invalid base64 padding".into());
+        }
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alphabet, from_base64, to_base64};
+
+    #[test]
+    fn round_trips_with_both_alphabets() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for alphabet in [Alphabet::Standard, Alphabet::UrlSafe] {
+            for len in 0..8 {
+                let text = to_base64(&bytes[250 - len..], alphabet);
+                assert_eq!(from_base64(&text, alphabet).unwrap(), &bytes[250 - len..]);
+            }
+        }
+    }
+
+    #[test]
+    fn alphabets_differ_in_the_last_two_symbols() {
+        assert_eq!(to_base64(&[0xfb, 0xff], Alphabet::Standard), "+/8=");
+        assert_eq!(to_base64(&[0xfb, 0xff], Alphabet::UrlSafe), "-_8=");
+        assert!(from_base64("+/8=", Alphabet::UrlSafe).is_err());
+        assert!(from_base64("-_8=", Alphabet::Standard).is_err());
+    }
+
+    #[test]
+    fn encodes_known_vectors() {
+        assert_eq!(to_base64(b"Man", Alphabet::Standard), "TWFu");
+        assert_eq!(to_base64(b"Ma", Alphabet::Standard), "TWE=");
+        assert_eq!(to_base64(b"M", Alphabet::Standard), "TQ==");
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for text in ["TW!u", "T===", "TWFuT", "TW=u"] {
+            assert!(from_base64(text, Alphabet::Standard).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn rejects_non_canonical_padding() {
+        for text in ["TQ", "TWE", "TQ=", "TWE==", "TQ===", "TR==", "TWF="] {
+            assert!(from_base64(text, Alphabet::Standard).is_err(), "{text}");
+        }
+    }
+}
@@ -1,12 +1,230 @@
-use crate::endian::Endianness;
+use crate::{endian::Endianness, error::Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LenPrefix {
+    U8,
+    U16,
+    U32,
+    U64,
+    I32,
+    I64,
+    Varint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsizeOverflow {
+    Error,
+    Saturate,
+}
+
+impl UsizeOverflow {
+    pub fn narrow(self, value: u64) -> Result<usize, Error> {
+        self.narrow_to(value, usize::MAX as u64)
+    }
+
+    pub fn narrow_to(self, value: u64, max: u64) -> Result<usize, Error> {
+        match self {
+            _ if value <= max => Ok(value as usize),
+            UsizeOverflow::Error => Err("usize overflow".into()),
+            UsizeOverflow::Saturate => Ok(max as usize),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Epoch {
+    Unix,
+    Windows,
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Context {
     pub endian: Endianness,
+    pub len: LenPrefix,
+    /// Overrides `len` for the next length prefix only, so a field's
+    /// `#[bin(len)]` does not reach the collections nested inside it.
+    pub outer_len: Option<LenPrefix>,
+    pub borrow_threshold: usize,
+    pub canonical: bool,
+    pub usize_overflow: UsizeOverflow,
+    pub verify_sorted: bool,
+    pub canonical_floats: bool,
+    pub max_preallocate: usize,
+    pub max_collection_len: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub depth: usize,
+    pub epoch: Epoch,
+    pub stream_version: Option<u16>,
+    pub strict_bool: bool,
+    /// Absolute position where the innermost sized frame ends. Decoders refuse
+    /// to read past it.
+    pub frame_end: Option<usize>,
+    /// End of a top-level value whose trailing `default` fields may be
+    /// omitted. Cleared by the value as soon as it starts decoding, so nested
+    /// values never see it.
+    pub value_end: Option<usize>,
 }
 
 impl Context {
     pub fn new(endian: Endianness) -> Self {
-        Self { endian }
+        Self {
+            endian,
+            len: LenPrefix::U32,
+            outer_len: None,
+            borrow_threshold: 0,
+            canonical: false,
+            usize_overflow: UsizeOverflow::Error,
+            verify_sorted: false,
+            canonical_floats: false,
+            max_preallocate: 1024,
+            max_collection_len: None,
+            max_depth: None,
+            depth: 0,
+            epoch: Epoch::Unix,
+            stream_version: None,
+            strict_bool: false,
+            frame_end: None,
+            value_end: None,
+        }
+    }
+
+    pub fn with_endian(self, endian: Endianness) -> Self {
+        Self { endian, ..self }
+    }
+
+    pub fn with_len(self, len: LenPrefix) -> Self {
+        Self { len, ..self }
+    }
+
+    pub fn with_outer_len(self, len: LenPrefix) -> Self {
+        Self {
+            outer_len: Some(len),
+            ..self
+        }
+    }
+
+    /// Returns the prefix for the next length, consuming any `outer_len`.
+    pub fn take_len(&mut self) -> LenPrefix {
+        self.outer_len.take().unwrap_or(self.len)
+    }
+
+    pub fn with_borrow_threshold(self, borrow_threshold: usize) -> Self {
+        Self {
+            borrow_threshold,
+            ..self
+        }
+    }
+
+    pub fn with_canonical(self, canonical: bool) -> Self {
+        Self { canonical, ..self }
+    }
+
+    pub fn with_usize_overflow(self, usize_overflow: UsizeOverflow) -> Self {
+        Self {
+            usize_overflow,
+            ..self
+        }
+    }
+
+    pub fn with_verify_sorted(self, verify_sorted: bool) -> Self {
+        Self {
+            verify_sorted,
+            ..self
+        }
+    }
+
+    pub fn with_canonical_floats(self, canonical_floats: bool) -> Self {
+        Self {
+            canonical_floats,
+            ..self
+        }
+    }
+
+    pub fn with_strict_bool(self, strict_bool: bool) -> Self {
+        Self {
+            strict_bool,
+            ..self
+        }
+    }
+
+    pub fn with_max_preallocate(self, max_preallocate: usize) -> Self {
+        Self {
+            max_preallocate,
+            ..self
+        }
+    }
+
+    pub fn with_max_collection_len(self, max_collection_len: usize) -> Self {
+        Self {
+            max_collection_len: Some(max_collection_len),
+            ..self
+        }
+    }
+
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..self
+        }
+    }
+
+    /// Limits an input length to the end of the innermost frame.
+    pub fn input_end(&self, len: usize) -> usize {
+        self.frame_end.map_or(len, |end| end.min(len))
+    }
+
+    pub fn preallocate(&self, len: usize) -> usize {
+        len.min(self.max_preallocate)
+    }
+
+    pub fn with_epoch(self, epoch: Epoch) -> Self {
+        Self { epoch, ..self }
+    }
+
+    pub fn with_stream_version(self, version: u16) -> Self {
+        Self {
+            stream_version: Some(version),
+            ..self
+        }
+    }
+
+    pub fn has_version(&self, since: u16) -> bool {
+        self.stream_version.is_none_or(|version| version >= since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, UsizeOverflow};
+    use crate::{decoder::Decode, endian::Endianness};
+
+    const U32_MAX: u64 = u32::MAX as u64;
+
+    #[test]
+    fn usize_overflow_errors_by_default() {
+        let policy = Context::new(Endianness::Little).usize_overflow;
+        assert_eq!(policy, UsizeOverflow::Error);
+        assert_eq!(
+            policy.narrow_to(U32_MAX, U32_MAX).unwrap(),
+            u32::MAX as usize
+        );
+        assert!(policy.narrow_to(U32_MAX + 1, U32_MAX).is_err());
+    }
+
+    #[test]
+    fn usize_overflow_saturates_when_configured() {
+        let policy = UsizeOverflow::Saturate;
+        assert_eq!(policy.narrow_to(7, U32_MAX).unwrap(), 7);
+        assert_eq!(
+            policy.narrow_to(u64::MAX, U32_MAX).unwrap(),
+            u32::MAX as usize
+        );
+    }
+
+    #[test]
+    fn usize_decode_consults_the_policy() {
+        let bytes = u64::MAX.to_le_bytes();
+        let ctx = Context::new(Endianness::Little).with_usize_overflow(UsizeOverflow::Saturate);
+        assert_eq!(usize::decode_with_ctx(&bytes, ctx).unwrap(), usize::MAX);
     }
 }
@@ -4,6 +4,9 @@ pub trait ByteConvertable<const N: usize>: Sized {
 
     fn to_be_bytes(self) -> [u8; N];
     fn from_be_bytes(bytes: [u8; N]) -> Self;
+
+    fn to_ne_bytes(self) -> [u8; N];
+    fn from_ne_bytes(bytes: [u8; N]) -> Self;
 }
 
 macro_rules! impl_convertable {
@@ -25,6 +28,14 @@ macro_rules! impl_convertable {
                 fn from_be_bytes(bytes: [u8; $len]) -> Self {
                     Self::from_be_bytes(bytes)
                 }
+
+                fn to_ne_bytes(self) -> [u8; $len] {
+                    self.to_ne_bytes()
+                }
+
+                fn from_ne_bytes(bytes: [u8; $len]) -> Self {
+                    Self::from_ne_bytes(bytes)
+                }
             }
         )*
     };
@@ -42,3 +53,51 @@ impl_convertable!(
     i128 => 16,
     u128 => 16
 );
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn little() -> Context {
+        Context::new(Endianness::Little)
+    }
+
+    fn big() -> Context {
+        Context::new(Endianness::Big)
+    }
+
+    macro_rules! endian_tests {
+        ($($name:ident: $ty:ty = $value:expr, |$v:ident| $swap:expr;)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let value: $ty = $value;
+                    let le = value.encode_with_ctx(little()).unwrap();
+                    let be = value.encode_with_ctx(big()).unwrap();
+                    assert_eq!(le.len(), size_of::<$ty>());
+                    assert_eq!(le.iter().rev().copied().collect::<Vec<_>>(), be);
+
+                    assert_eq!(<$ty>::decode_with_ctx(&le, little()).unwrap().to_ne_bytes(), value.to_ne_bytes());
+                    assert_eq!(<$ty>::decode_with_ctx(&be, big()).unwrap().to_ne_bytes(), value.to_ne_bytes());
+
+                    let swapped = { let $v = value; $swap };
+                    assert_eq!(<$ty>::decode_with_ctx(&le, big()).unwrap().to_ne_bytes(), swapped.to_ne_bytes());
+                    assert_eq!(<$ty>::decode_with_ctx(&be, little()).unwrap().to_ne_bytes(), swapped.to_ne_bytes());
+                }
+            )*
+        };
+    }
+
+    endian_tests! {
+        u16_honors_endianness: u16 = 0x0102, |v| v.swap_bytes();
+        i16_honors_endianness: i16 = -0x0102, |v| v.swap_bytes();
+        u32_honors_endianness: u32 = 0x0102_0304, |v| v.swap_bytes();
+        i32_honors_endianness: i32 = -0x0102_0304, |v| v.swap_bytes();
+        u64_honors_endianness: u64 = 0x0102_0304_0506_0708, |v| v.swap_bytes();
+        i64_honors_endianness: i64 = -0x0102_0304_0506_0708, |v| v.swap_bytes();
+        u128_honors_endianness: u128 = 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, |v| v.swap_bytes();
+        i128_honors_endianness: i128 = -0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, |v| v.swap_bytes();
+        f32_honors_endianness: f32 = 1.5, |v| f32::from_bits(v.to_bits().swap_bytes());
+        f64_honors_endianness: f64 = -2.25, |v| f64::from_bits(v.to_bits().swap_bytes());
+    }
+}
@@ -0,0 +1,78 @@
+use std::hash::Hasher;
+
+use crate::error::Error;
+
+const POLYNOMIAL: u32 = 0xedb8_8320;
+
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self(!0)
+    }
+}
+
+impl Crc32 {
+    pub fn value(&self) -> u32 {
+        !self.0
+    }
+}
+
+impl Hasher for Crc32 {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = TABLE[((self.0 ^ *byte as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.value() as u64
+    }
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::default();
+    crc.write(bytes);
+    crc.value()
+}
+
+pub fn append_checksum(mut bytes: Vec<u8>) -> Vec<u8> {
+    let crc = crc32(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+pub fn strip_checksum(bytes: &[u8]) -> Result<&[u8], Error> {
+    if bytes.len() < 4 {
+        return Err(Error::UnexpectedEof {
+            needed: 4,
+            remaining: bytes.len(),
+        });
+    }
+    let (payload, trailer) = bytes.split_at(bytes.len() - 4);
+    if crc32(payload).to_le_bytes() != trailer {
+        return Err("Checksum mismatch".into());
+    }
+    Ok(payload)
+}
@@ -1,21 +1,142 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+    ffi::CString,
+    io::Read,
+    marker::PhantomData,
+    num::{
+        NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroU8, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU128, Saturating, Wrapping,
+    },
+    ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicBool, AtomicI8, AtomicI16, AtomicI32, AtomicI64, AtomicU8, AtomicU16, AtomicU32,
+            AtomicU64, AtomicUsize,
+        },
+    },
+};
 
-use crate::{context::Context, converter::ByteConvertable, endian::Endianness, error::Error};
+use crate::{
+    context::{Context, LenPrefix},
+    converter::ByteConvertable,
+    endian::Endianness,
+    error::Error,
+    hex,
+    private::sum_fixed_sizes,
+    shared::DecodeTable,
+    varint,
+};
+
+macro_rules! impl_endian_decoders {
+    ($($ty:ty => $le:ident, $be:ident), *) => {
+        $(
+            fn $le(&mut self) -> Result<$ty, Error> {
+                self.decode_endian(Endianness::Little)
+            }
+
+            fn $be(&mut self) -> Result<$ty, Error> {
+                self.decode_endian(Endianness::Big)
+            }
+        )*
+    };
+}
 
 pub trait Decoder {
     fn context(&self) -> Context;
 
+    fn set_context(&mut self, context: Context);
+
     fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error>;
 
+    fn position(&self) -> usize;
+
+    fn peek_bytes(&self, _len: usize) -> Result<&[u8], Error> {
+        Err("Peeking is not supported by this decoder".into())
+    }
+
+    fn peek_u8(&self) -> Result<u8, Error> {
+        Ok(self.peek_bytes(1)?[0])
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn progress(&self) -> Option<f64> {
+        match self.total_len()? {
+            0 => Some(1.0),
+            total => Some(self.position() as f64 / total as f64),
+        }
+    }
+
+    fn begin_field(&mut self, _name: &str) {}
+
+    fn end_field(&mut self, _name: &str) {}
+
+    fn shared_table(&mut self) -> Option<&mut DecodeTable> {
+        None
+    }
+
+    fn track_alloc(&mut self, _bytes: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        let context = self.context();
+        if context.max_depth.is_some_and(|max| context.depth >= max) {
+            return Err(Error::DepthLimitExceeded);
+        }
+        self.set_context(Context {
+            depth: context.depth + 1,
+            ..context
+        });
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        let context = self.context();
+        self.set_context(Context {
+            depth: context.depth.saturating_sub(1),
+            ..context
+        });
+    }
+
+    fn error(&self, error: impl Into<Error>) -> Error {
+        Error::at(self.position(), error)
+    }
+
     fn decode<T, const N: usize>(&mut self) -> Result<T, Error>
     where
         T: ByteConvertable<N>,
     {
-        let bytes = self.decode_bytes(std::mem::size_of::<T>())?;
+        self.decode_endian(self.context().endian)
+    }
+
+    fn decode_endian<T, const N: usize>(&mut self, endian: Endianness) -> Result<T, Error>
+    where
+        T: ByteConvertable<N>,
+    {
+        let bytes = self.decode_bytes(N)?;
         let bytes: [u8; N] = bytes.try_into().map_err(|_| "Invalid Length")?;
-        Ok(self.context().endian.from_bytes(bytes))
+        Ok(endian.from_bytes(bytes))
     }
 
+    impl_endian_decoders!(
+        i16 => decode_i16_le, decode_i16_be,
+        u16 => decode_u16_le, decode_u16_be,
+        i32 => decode_i32_le, decode_i32_be,
+        u32 => decode_u32_le, decode_u32_be,
+        i64 => decode_i64_le, decode_i64_be,
+        u64 => decode_u64_le, decode_u64_be,
+        i128 => decode_i128_le, decode_i128_be,
+        u128 => decode_u128_le, decode_u128_be,
+        f32 => decode_f32_le, decode_f32_be,
+        f64 => decode_f64_le, decode_f64_be
+    );
+
     fn decode_i8(&mut self) -> Result<i8, Error> {
         let bytes = self.decode_bytes(1)?;
         Ok(bytes[0] as i8)
@@ -58,8 +179,9 @@ pub trait Decoder {
         self.decode()
     }
 
-    fn decode_usize(&mut self) -> Result<u128, Error> {
-        self.decode()
+    fn decode_usize(&mut self) -> Result<usize, Error> {
+        let value = self.decode_u64()?;
+        self.context().usize_overflow.narrow(value)
     }
 
     fn decode_f32(&mut self) -> Result<f32, Error> {
@@ -71,14 +193,153 @@ pub trait Decoder {
     }
 
     fn decode_bool(&mut self) -> Result<bool, Error> {
+        if self.context().strict_bool {
+            return self.decode_bool_strict();
+        }
         let bytes = self.decode_bytes(1)?;
         Ok(bytes[0] != 0)
     }
 
+    fn decode_bool_strict(&mut self) -> Result<bool, Error> {
+        match self.decode_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err("Invalid bool".into()),
+        }
+    }
+
+    fn decode_len(&mut self) -> Result<usize, Error> {
+        let mut context = self.context();
+        let prefix = context.take_len();
+        self.set_context(context);
+        let len = match prefix {
+            LenPrefix::U8 => self.decode_u8()? as u64,
+            LenPrefix::U16 => self.decode_u16()? as u64,
+            LenPrefix::U32 => self.decode_u32()? as u64,
+            LenPrefix::U64 => self.decode_u64()?,
+            LenPrefix::I32 => self.decode_len_signed::<i32, 4>()? as u64,
+            LenPrefix::I64 => self.decode_len_signed::<i64, 8>()? as u64,
+            LenPrefix::Varint => varint::decode_varint_u64(self)?,
+        };
+        let len = usize::try_from(len).map_err(|_| "Length out of range")?;
+        if self
+            .context()
+            .max_collection_len
+            .is_some_and(|max| len > max)
+        {
+            return Err("Collection too large".into());
+        }
+        Ok(len)
+    }
+
+    fn decode_len_signed<T, const N: usize>(&mut self) -> Result<usize, Error>
+    where
+        T: ByteConvertable<N> + Into<i64>,
+    {
+        let len: i64 = self.decode::<T, N>()?.into();
+        if len < 0 {
+            return Err("Negative length".into());
+        }
+        Ok(usize::try_from(len).map_err(|_| "Length out of range")?)
+    }
+
     fn decode_string(&mut self) -> Result<String, Error> {
-        let len = self.decode_u32()? as usize;
-        let bytes = self.decode_bytes(len)?;
-        Ok(String::from_utf8(bytes.to_vec())?)
+        let len = self.decode_len()?;
+        self.decode_string_fixed(len)
+    }
+
+    fn decode_string_fixed(&mut self, len: usize) -> Result<String, Error> {
+        ensure_remaining(self, len)?;
+        self.track_alloc(len)?;
+        let bytes = self.decode_bytes(len)?.to_vec();
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn decode_string_padded(&mut self, width: usize) -> Result<String, Error> {
+        ensure_remaining(self, width)?;
+        self.track_alloc(width)?;
+        let bytes = self.decode_bytes(width)?;
+        let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        let bytes = bytes[..len].to_vec();
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn read_version_header(&mut self) -> Result<u16, Error> {
+        let version = self.decode_u16()?;
+        self.set_context(self.context().with_stream_version(version));
+        Ok(version)
+    }
+
+    fn decode_cstr(&mut self) -> Result<String, Error> {
+        Ok(String::from_utf8(decode_nul_terminated(self)?)?)
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        let end = input_end(self).ok_or("Remaining length is unknown for this decoder")?;
+        self.decode_bytes(end.saturating_sub(self.position()))
+    }
+
+    fn skip_bytes(&mut self, len: usize) -> Result<(), Error> {
+        self.decode_bytes(len)?;
+        Ok(())
+    }
+
+    fn align_to(&mut self, alignment: usize) -> Result<(), Error> {
+        if alignment == 0 {
+            return Err("Alignment must be non-zero".into());
+        }
+        match self.position() % alignment {
+            0 => Ok(()),
+            rem => self.skip_bytes(alignment - rem),
+        }
+    }
+
+    fn expect_padding(&mut self, len: usize, byte: u8) -> Result<(), Error> {
+        if self.decode_bytes(len)?.iter().any(|b| *b != byte) {
+            return Err("Invalid padding".into());
+        }
+        Ok(())
+    }
+
+    fn decode_map_entries<K, V>(&mut self) -> Result<MapEntriesIter<'_, Self, K, V>, Error>
+    where
+        Self: Sized,
+        K: Decode,
+        V: Decode,
+    {
+        let len = self.decode_len()?;
+        Ok(MapEntriesIter {
+            decoder: self,
+            remaining: len,
+            _marker: PhantomData,
+        })
+    }
+}
+
+pub struct MapEntriesIter<'d, D, K, V> {
+    decoder: &'d mut D,
+    remaining: usize,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<D: Decoder, K: Decode, V: Decode> Iterator for MapEntriesIter<'_, D, K, V> {
+    type Item = Result<(K, V), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let entry = K::decode(self.decoder).and_then(|key| Ok((key, V::decode(self.decoder)?)));
+        if entry.is_err() {
+            self.remaining = 0;
+        }
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
     }
 }
 
@@ -93,14 +354,24 @@ impl<'a> Decoder for BufferDecoder<'a> {
         self.context
     }
 
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
     fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
-        if self.position + len > self.buffer.len() {
-            return Err("Not enough bytes to decode".into());
-        }
+        self.decode_borrowed_bytes(len)
+    }
 
-        let slice = &self.buffer[self.position..self.position + len];
-        self.position += len;
-        Ok(slice)
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        self.peek_borrowed_bytes(len)
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        Some(self.buffer.len())
     }
 }
 
@@ -122,7 +393,9 @@ impl<'a> BufferDecoder<'a> {
     }
 
     pub fn remaining(&self) -> usize {
-        self.buffer.len().saturating_sub(self.position)
+        self.context
+            .input_end(self.buffer.len())
+            .saturating_sub(self.position)
     }
 
     pub fn position(&self) -> usize {
@@ -132,149 +405,668 @@ impl<'a> BufferDecoder<'a> {
     pub fn set_position(&mut self, pos: usize) {
         self.position = pos.min(self.buffer.len());
     }
+
+    pub fn peek_borrowed_bytes(&self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.remaining() {
+            return Err(Error::at(
+                self.position,
+                Error::UnexpectedEof {
+                    needed: len,
+                    remaining: self.remaining(),
+                },
+            ));
+        }
+
+        Ok(&self.buffer[self.position..self.position + len])
+    }
+
+    pub fn decode_borrowed_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let slice = self.peek_borrowed_bytes(len)?;
+        self.position += len;
+        Ok(slice)
+    }
+
+    pub fn decode_cow_bytes(&mut self) -> Result<Cow<'a, [u8]>, Error> {
+        let len = self.decode_len()?;
+        let bytes = self.decode_borrowed_bytes(len)?;
+        if len < self.context.borrow_threshold {
+            Ok(Cow::Owned(bytes.to_vec()))
+        } else {
+            Ok(Cow::Borrowed(bytes))
+        }
+    }
+
+    pub fn decode_cow_str(&mut self) -> Result<Cow<'a, str>, Error> {
+        match self.decode_cow_bytes()? {
+            Cow::Borrowed(bytes) => Ok(Cow::Borrowed(std::str::from_utf8(bytes)?)),
+            Cow::Owned(bytes) => Ok(Cow::Owned(String::from_utf8(bytes)?)),
+        }
+    }
+}
+
+pub struct OwnedBufferDecoder {
+    buffer: Vec<u8>,
+    position: usize,
+    context: Context,
+}
+
+impl Decoder for OwnedBufferDecoder {
+    fn context(&self) -> Context {
+        self.context
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        self.peek_bytes(len)?;
+        let start = self.position;
+        self.position += len;
+        Ok(&self.buffer[start..self.position])
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        if len > self.remaining() {
+            return Err(Error::at(
+                self.position,
+                Error::UnexpectedEof {
+                    needed: len,
+                    remaining: self.remaining(),
+                },
+            ));
+        }
+
+        Ok(&self.buffer[self.position..self.position + len])
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        Some(self.buffer.len())
+    }
+}
+
+impl OwnedBufferDecoder {
+    pub fn new(buffer: Vec<u8>) -> Self {
+        Self::with_ctx(buffer, Context::new(Endianness::Little))
+    }
+
+    pub fn with_ctx(buffer: Vec<u8>, context: Context) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            context,
+        }
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R, context: Context) -> Result<Self, Error> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        Ok(Self::with_ctx(buffer, context))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.context
+            .input_end(self.buffer.len())
+            .saturating_sub(self.position)
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+// `decode_bytes` reads into a scratch buffer that is reused by the next call, so
+// each returned slice is only valid until the decoder is used again.
+pub struct ReadDecoder<R: Read> {
+    reader: R,
+    scratch: Vec<u8>,
+    position: usize,
+    context: Context,
+}
+
+impl<R: Read> Decoder for ReadDecoder<R> {
+    fn context(&self) -> Context {
+        self.context
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        if let Some(end) = self.context.frame_end
+            && len > end.saturating_sub(self.position)
+        {
+            return Err(Error::at(
+                self.position,
+                Error::UnexpectedEof {
+                    needed: len,
+                    remaining: end.saturating_sub(self.position),
+                },
+            ));
+        }
+        self.scratch.clear();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut self.scratch)?;
+        if self.scratch.len() < len {
+            return Err(Error::at(
+                self.position,
+                Error::UnexpectedEof {
+                    needed: len,
+                    remaining: self.scratch.len(),
+                },
+            ));
+        }
+        self.position += len;
+        Ok(&self.scratch)
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        if let Some(end) = self.context.frame_end {
+            return self.decode_bytes(end.saturating_sub(self.position));
+        }
+        self.scratch.clear();
+        self.reader.read_to_end(&mut self.scratch)?;
+        self.position += self.scratch.len();
+        Ok(&self.scratch)
+    }
+}
+
+impl<R: Read> ReadDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_ctx(reader, Context::new(Endianness::Little))
+    }
+
+    pub fn with_ctx(reader: R, context: Context) -> Self {
+        Self {
+            reader,
+            scratch: Vec::new(),
+            position: 0,
+            context,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
 }
 
 pub trait Decode: Sized {
+    const MIN_SIZE: usize = 0;
+
+    const FIXED_SIZE: Option<usize> = None;
+
+    /// Set by the derive for types whose trailing `default` fields may be
+    /// missing from the input, so the top-level entry points tell them where
+    /// the input ends.
+    #[doc(hidden)]
+    const TRAILING_DEFAULTS: bool = false;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error>;
 
     fn decode_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let mut decoder = BufferDecoder::new(bytes);
-        Self::decode(&mut decoder)
+        Self::decode_with_ctx(bytes, Context::new(Endianness::Little))
     }
 
     fn decode_with_ctx(bytes: &[u8], ctx: Context) -> Result<Self, Error> {
+        if bytes.is_empty() && Self::MIN_SIZE > 0 {
+            return Err(Error::EmptyInput);
+        }
+        let ctx = if Self::TRAILING_DEFAULTS {
+            Context {
+                value_end: Some(bytes.len()),
+                ..ctx
+            }
+        } else {
+            ctx
+        };
         let mut decoder = BufferDecoder::with_ctx(bytes, ctx);
         Self::decode(&mut decoder)
     }
+
+    fn decode_from_hex(hex: &str) -> Result<Self, Error> {
+        Self::decode_from_bytes(&hex::from_hex(hex)?)
+    }
+
+    #[cfg(feature = "base64")]
+    fn decode_from_base64(text: &str, alphabet: crate::base64::Alphabet) -> Result<Self, Error> {
+        Self::decode_from_bytes(&crate::base64::from_base64(text, alphabet)?)
+    }
+
+    #[cfg(feature = "crc32")]
+    fn decode_from_bytes_checked(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode_from_bytes(crate::crc32::strip_checksum(bytes)?)
+    }
+}
+
+pub trait DecodeBorrowed<'a>: Sized {
+    fn decode_borrowed(decoder: &mut BufferDecoder<'a>) -> Result<Self, Error>;
+
+    fn decode_from_borrowed_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::decode_borrowed(&mut BufferDecoder::new(bytes))
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a [u8] {
+    fn decode_borrowed(decoder: &mut BufferDecoder<'a>) -> Result<Self, Error> {
+        let len = decoder.decode_len()?;
+        decoder.decode_borrowed_bytes(len)
+    }
+}
+
+impl<'a> DecodeBorrowed<'a> for &'a str {
+    fn decode_borrowed(decoder: &mut BufferDecoder<'a>) -> Result<Self, Error> {
+        Ok(std::str::from_utf8(<&[u8]>::decode_borrowed(decoder)?)?)
+    }
+}
+
+pub trait DecodeSeed: Sized {
+    type Value;
+
+    fn decode_seed<D: Decoder>(self, decoder: &mut D) -> Result<Self::Value, Error>;
+}
+
+impl<T: Decode> DecodeSeed for PhantomData<T> {
+    type Value = T;
+
+    fn decode_seed<D: Decoder>(self, decoder: &mut D) -> Result<Self::Value, Error> {
+        T::decode(decoder)
+    }
 }
 
 impl Decode for u8 {
+    const MIN_SIZE: usize = 1;
+    const FIXED_SIZE: Option<usize> = Some(1);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_u8()
     }
 }
 
 impl Decode for i8 {
+    const MIN_SIZE: usize = 1;
+    const FIXED_SIZE: Option<usize> = Some(1);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_i8()
     }
 }
 
 impl Decode for u16 {
+    const MIN_SIZE: usize = 2;
+    const FIXED_SIZE: Option<usize> = Some(2);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_u16()
     }
 }
 
 impl Decode for i16 {
+    const MIN_SIZE: usize = 2;
+    const FIXED_SIZE: Option<usize> = Some(2);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_i16()
     }
 }
 
 impl Decode for u32 {
+    const MIN_SIZE: usize = 4;
+    const FIXED_SIZE: Option<usize> = Some(4);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_u32()
     }
 }
 
 impl Decode for i32 {
+    const MIN_SIZE: usize = 4;
+    const FIXED_SIZE: Option<usize> = Some(4);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_i32()
     }
 }
 
 impl Decode for u64 {
+    const MIN_SIZE: usize = 8;
+    const FIXED_SIZE: Option<usize> = Some(8);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_u64()
     }
 }
 
+impl Decode for usize {
+    const MIN_SIZE: usize = 8;
+    const FIXED_SIZE: Option<usize> = Some(8);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        decoder.decode_usize()
+    }
+}
+
 impl Decode for i64 {
+    const MIN_SIZE: usize = 8;
+    const FIXED_SIZE: Option<usize> = Some(8);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_i64()
     }
 }
 
 impl Decode for u128 {
+    const MIN_SIZE: usize = 16;
+    const FIXED_SIZE: Option<usize> = Some(16);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_u128()
     }
 }
 
 impl Decode for i128 {
+    const MIN_SIZE: usize = 16;
+    const FIXED_SIZE: Option<usize> = Some(16);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_i128()
     }
 }
 
 impl Decode for f32 {
+    const MIN_SIZE: usize = 4;
+    const FIXED_SIZE: Option<usize> = Some(4);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_f32()
     }
 }
 
 impl Decode for f64 {
+    const MIN_SIZE: usize = 8;
+    const FIXED_SIZE: Option<usize> = Some(8);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_f64()
     }
 }
 
 impl Decode for bool {
+    const MIN_SIZE: usize = 1;
+    const FIXED_SIZE: Option<usize> = Some(1);
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_bool()
     }
 }
 
+macro_rules! impl_nonzero_decode {
+    ($($ty:ident),*) => {
+        $(
+            impl Decode for $ty {
+                const MIN_SIZE: usize = size_of::<$ty>();
+                const FIXED_SIZE: Option<usize> = Some(size_of::<$ty>());
+
+                fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+                    $ty::new(Decode::decode(decoder)?).ok_or_else(|| "Unexpected zero for NonZero".into())
+                }
+            }
+        )*
+    };
+}
+
+impl_nonzero_decode!(
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128
+);
+
+macro_rules! impl_atomic_decode {
+    ($($ty:ty => $inner:ty),*) => {
+        $(
+            impl Decode for $ty {
+                const MIN_SIZE: usize = <$inner>::MIN_SIZE;
+                const FIXED_SIZE: Option<usize> = <$inner>::FIXED_SIZE;
+
+                fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+                    Ok(<$ty>::new(<$inner>::decode(decoder)?))
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_decode!(
+    AtomicBool => bool,
+    AtomicU8 => u8,
+    AtomicU16 => u16,
+    AtomicU32 => u32,
+    AtomicU64 => u64,
+    AtomicUsize => usize,
+    AtomicI8 => i8,
+    AtomicI16 => i16,
+    AtomicI32 => i32,
+    AtomicI64 => i64
+);
+
+impl<T: Decode> Decode for Wrapping<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Wrapping(T::decode(decoder)?))
+    }
+}
+
+impl<T: Decode> Decode for Saturating<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Saturating(T::decode(decoder)?))
+    }
+}
+
+impl Decode for char {
+    const MIN_SIZE: usize = 4;
+    const FIXED_SIZE: Option<usize> = Some(4);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        char::from_u32(decoder.decode_u32()?).ok_or_else(|| "Invalid char".into())
+    }
+}
+
 impl Decode for String {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
         decoder.decode_string()
     }
 }
 
-impl<T: Decode> Decode for (T, T) {
+pub(crate) fn decode_nul_terminated<D: Decoder + ?Sized>(
+    decoder: &mut D,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    loop {
+        match decoder.decode_u8()? {
+            0 => return Ok(bytes),
+            byte => bytes.push(byte),
+        }
+    }
+}
+
+impl Decode for CString {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let a = T::decode(decoder)?;
-        let b = T::decode(decoder)?;
-        Ok((a, b))
+        Ok(CString::new(decode_nul_terminated(decoder)?)?)
     }
 }
 
-impl<T: Decode> Decode for (T, T, T) {
+impl<B: ToOwned + ?Sized> Decode for Cow<'_, B>
+where
+    B::Owned: Decode,
+{
+    const MIN_SIZE: usize = B::Owned::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = B::Owned::FIXED_SIZE;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let a = T::decode(decoder)?;
-        let b = T::decode(decoder)?;
-        let c = T::decode(decoder)?;
-        Ok((a, b, c))
+        Ok(Cow::Owned(B::Owned::decode(decoder)?))
     }
 }
 
+macro_rules! impl_tuple_decode {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: Decode),+> Decode for ($($name,)+) {
+                const MIN_SIZE: usize = 0 $(+ $name::MIN_SIZE)+;
+                const FIXED_SIZE: Option<usize> = sum_fixed_sizes(&[$($name::FIXED_SIZE),+]);
+
+                fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+                    Ok(($($name::decode(decoder)?,)+))
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple_decode!(
+    (T0),
+    (T0, T1),
+    (T0, T1, T2),
+    (T0, T1, T2, T3),
+    (T0, T1, T2, T3, T4),
+    (T0, T1, T2, T3, T4, T5)
+);
+
 impl<T: Decode> Decode for Option<T> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let start = decoder.position();
         let tag = u8::decode(decoder)?;
         match tag {
             0 => Ok(None),
             1 => Ok(Some(T::decode(decoder)?)),
-            _ => Err("Invalid Option Tag".into()),
+            _ => Err(Error::at(start, Error::InvalidTag)),
         }
     }
 }
 
 impl<T: Decode, E: Decode> Decode for Result<T, E> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let start = decoder.position();
         let tag = u8::decode(decoder)?;
         match tag {
             1 => Ok(Ok(T::decode(decoder)?)),
             0 => Ok(Err(E::decode(decoder)?)),
-            _ => Err("Invalid Result Tag".into()),
+            _ => Err(Error::at(start, Error::InvalidTag)),
         }
     }
 }
 
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    const MIN_SIZE: usize = N * T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = match T::FIXED_SIZE {
+        Some(size) => Some(N * size),
+        None => None,
+    };
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        // Collecting through a Vec drops the already decoded elements if a
+        // later one fails, without requiring `T: Default` or `T: Copy`.
+        let items = (0..N)
+            .map(|_| T::decode(decoder))
+            .collect::<Result<Vec<_>, Error>>()?;
+        items.try_into().map_err(|_| "Invalid Length".into())
+    }
+}
+
+/// Where the readable input ends: the end of the innermost frame or of the
+/// whole input, whichever comes first.
+fn input_end<D: Decoder + ?Sized>(decoder: &D) -> Option<usize> {
+    let context = decoder.context();
+    match decoder.total_len() {
+        Some(len) => Some(context.input_end(len)),
+        None => context.frame_end,
+    }
+}
+
+/// Rejects a length the input cannot hold before it is charged to the
+/// allocation budget. Decoders without a known length are left to fail in
+/// `decode_bytes`.
+fn ensure_remaining<D: Decoder + ?Sized>(decoder: &D, len: usize) -> Result<(), Error> {
+    let Some(end) = input_end(decoder) else {
+        return Ok(());
+    };
+    let remaining = end.saturating_sub(decoder.position());
+    if len > remaining {
+        return Err(decoder.error(Error::UnexpectedEof {
+            needed: len,
+            remaining,
+        }));
+    }
+    Ok(())
+}
+
+pub(crate) fn preallocate<T, D: Decoder>(decoder: &mut D, len: usize) -> Result<usize, Error> {
+    let capacity = decoder.context().preallocate(len);
+    decoder.track_alloc(capacity.saturating_mul(size_of::<T>()))?;
+    Ok(capacity)
+}
+
+// With a known input length, a fixed element size lets the whole collection be
+// checked against the input up front and reserved exactly. The elements are
+// still decoded through `decoder` so hooks, shared tables and offsets apply.
+fn decode_fixed_elements<T, D>(decoder: &mut D, len: usize, size: usize) -> Result<Vec<T>, Error>
+where
+    T: Decode,
+    D: Decoder,
+{
+    let total = len.checked_mul(size).ok_or("Collection too large")?;
+    ensure_remaining(decoder, total)?;
+    let capacity = match input_end(decoder) {
+        Some(_) => len,
+        None => decoder.context().preallocate(len),
+    };
+    decoder.track_alloc(capacity.saturating_mul(size_of::<T>()))?;
+    let mut vec = Vec::with_capacity(capacity);
+    for _ in 0..len {
+        vec.push(T::decode(decoder)?);
+    }
+    Ok(vec)
+}
+
 impl<T: Decode> Decode for Vec<T> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let len = u32::decode(decoder)? as usize;
-        let mut vec = Vec::with_capacity(len);
+        let len = decoder.decode_len()?;
+        if let Some(size) = T::FIXED_SIZE
+            && size > 0
+        {
+            return decode_fixed_elements(decoder, len, size);
+        }
+        let mut vec = Vec::with_capacity(preallocate::<T, _>(decoder, len)?);
         for _ in 0..len {
             vec.push(T::decode(decoder)?);
         }
@@ -282,10 +1074,36 @@ impl<T: Decode> Decode for Vec<T> {
     }
 }
 
+impl<T: Decode> Decode for VecDeque<T> {
+    const MIN_SIZE: usize = Vec::<T>::MIN_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Vec::<T>::decode(decoder)?.into())
+    }
+}
+
+impl<T: Decode> Decode for LinkedList<T> {
+    const MIN_SIZE: usize = Vec::<T>::MIN_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Vec::<T>::decode(decoder)?.into_iter().collect())
+    }
+}
+
+impl<T: Decode + Ord> Decode for BinaryHeap<T> {
+    const MIN_SIZE: usize = Vec::<T>::MIN_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Vec::<T>::decode(decoder)?.into())
+    }
+}
+
 impl<T: Decode + Eq + std::hash::Hash> Decode for HashSet<T> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let len = u32::decode(decoder)? as usize;
-        let mut set = HashSet::with_capacity(len);
+        let len = decoder.decode_len()?;
+        let mut set = HashSet::with_capacity(preallocate::<T, _>(decoder, len)?);
         for _ in 0..len {
             set.insert(T::decode(decoder)?);
         }
@@ -294,20 +1112,29 @@ impl<T: Decode + Eq + std::hash::Hash> Decode for HashSet<T> {
 }
 
 impl<T: Decode + Ord> Decode for BTreeSet<T> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let len = u32::decode(decoder)? as usize;
+        let len = decoder.decode_len()?;
+        let verify_sorted = decoder.context().verify_sorted;
         let mut set = BTreeSet::new();
         for _ in 0..len {
-            set.insert(T::decode(decoder)?);
+            let item = T::decode(decoder)?;
+            if verify_sorted && set.last().is_some_and(|last| item <= *last) {
+                return Err("Keys out of order".into());
+            }
+            set.insert(item);
         }
         Ok(set)
     }
 }
 
 impl<K: Decode + Eq + std::hash::Hash, V: Decode> Decode for HashMap<K, V> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let len = u32::decode(decoder)? as usize;
-        let mut map = HashMap::with_capacity(len);
+        let len = decoder.decode_len()?;
+        let mut map = HashMap::with_capacity(preallocate::<(K, V), _>(decoder, len)?);
         for _ in 0..len {
             map.insert(K::decode(decoder)?, V::decode(decoder)?);
         }
@@ -316,12 +1143,584 @@ impl<K: Decode + Eq + std::hash::Hash, V: Decode> Decode for HashMap<K, V> {
 }
 
 impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    const MIN_SIZE: usize = 1;
+
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
-        let len = u32::decode(decoder)? as usize;
+        let len = decoder.decode_len()?;
+        let verify_sorted = decoder.context().verify_sorted;
         let mut map = BTreeMap::new();
         for _ in 0..len {
-            map.insert(K::decode(decoder)?, V::decode(decoder)?);
+            let key = K::decode(decoder)?;
+            if verify_sorted && map.last_key_value().is_some_and(|(last, _)| key <= *last) {
+                return Err("Keys out of order".into());
+            }
+            map.insert(key, V::decode(decoder)?);
         }
         Ok(map)
     }
 }
+
+impl<T: Decode> Decode for Range<T> {
+    const MIN_SIZE: usize = 2 * T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = sum_fixed_sizes(&[T::FIXED_SIZE, T::FIXED_SIZE]);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let start = T::decode(decoder)?;
+        let end = T::decode(decoder)?;
+        Ok(start..end)
+    }
+}
+
+impl<T: Decode> Decode for RangeFrom<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(T::decode(decoder)?..)
+    }
+}
+
+impl<T: Decode> Decode for RangeTo<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(..T::decode(decoder)?)
+    }
+}
+
+impl<T: Decode> Decode for RangeInclusive<T> {
+    const MIN_SIZE: usize = 2 * T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = sum_fixed_sizes(&[T::FIXED_SIZE, T::FIXED_SIZE]);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let start = T::decode(decoder)?;
+        let end = T::decode(decoder)?;
+        Ok(start..=end)
+    }
+}
+
+impl Decode for RangeFull {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    fn decode<D: Decoder>(_decoder: &mut D) -> Result<Self, Error> {
+        Ok(..)
+    }
+}
+
+impl<T: Decode> Decode for Rc<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Rc::new(T::decode(decoder)?))
+    }
+}
+
+impl<T: Decode> Decode for Arc<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Arc::new(T::decode(decoder)?))
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Box::new(T::decode(decoder)?))
+    }
+}
+
+impl Decode for Box<str> {
+    const MIN_SIZE: usize = 1;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(decoder.decode_string()?.into_boxed_str())
+    }
+}
+
+impl<T: Decode> Decode for Box<[T]> {
+    const MIN_SIZE: usize = Vec::<T>::MIN_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Vec::decode(decoder)?.into_boxed_slice())
+    }
+}
+
+impl Decode for Rc<str> {
+    const MIN_SIZE: usize = 1;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Rc::from(decoder.decode_string()?))
+    }
+}
+
+impl Decode for Arc<str> {
+    const MIN_SIZE: usize = 1;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Arc::from(decoder.decode_string()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap},
+        io::Cursor,
+        marker::PhantomData,
+        ops::{RangeFrom, RangeFull, RangeInclusive, RangeTo},
+        rc::Rc,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use super::{OwnedBufferDecoder, ReadDecoder};
+    use crate::{instrument::InstrumentedCodec, prelude::*, testing::AllocBudget};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Expr {
+        Lit(i64),
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+    }
+
+    fn nested(depth: usize) -> Expr {
+        (0..depth).fold(Expr::Lit(0), |expr, _| Expr::Neg(Box::new(expr)))
+    }
+
+    #[test]
+    fn recursive_enum_round_trips() {
+        let expr = Expr::Add(Box::new(Expr::Lit(1)), Box::new(nested(8)));
+        let bytes = expr.encode_to_bytes().unwrap();
+        assert_eq!(Expr::decode_from_bytes(&bytes).unwrap(), expr);
+    }
+
+    #[test]
+    fn recursive_enum_respects_max_depth() {
+        let ctx = Context::new(Endianness::Little).with_max_depth(16);
+
+        let bytes = nested(15).encode_with_ctx(ctx).unwrap();
+        assert_eq!(Expr::decode_with_ctx(&bytes, ctx).unwrap(), nested(15));
+
+        let bytes = nested(64).encode_with_ctx(ctx).unwrap();
+        let err = Expr::decode_with_ctx(&bytes, ctx).unwrap_err();
+        assert!(matches!(err.kind(), Error::DepthLimitExceeded));
+    }
+
+    #[test]
+    fn open_ranges_round_trip() {
+        let bytes = (3u32..).encode_to_bytes().unwrap();
+        assert_eq!(bytes, 3u32.encode_to_bytes().unwrap());
+        assert_eq!(RangeFrom::<u32>::decode_from_bytes(&bytes).unwrap(), 3..);
+
+        let bytes = (..7u16).encode_to_bytes().unwrap();
+        assert_eq!(bytes, 7u16.encode_to_bytes().unwrap());
+        assert_eq!(RangeTo::<u16>::decode_from_bytes(&bytes).unwrap(), ..7);
+
+        assert!((..).encode_to_bytes().unwrap().is_empty());
+        assert_eq!(RangeFull::decode_from_bytes(&[]).unwrap(), ..);
+    }
+
+    #[test]
+    fn inclusive_ranges_round_trip_their_bounds() {
+        for range in [2u8..=9, 4..=4, RangeInclusive::new(9, 2)] {
+            let bytes = range.encode_to_bytes().unwrap();
+            assert_eq!(bytes, [*range.start(), *range.end()]);
+            let decoded = RangeInclusive::<u8>::decode_from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, range);
+            assert!(decoded.eq(range));
+        }
+    }
+
+    #[test]
+    fn exhausted_inclusive_range_decodes_as_a_fresh_range() {
+        let mut range = 1u8..=3;
+        assert_eq!(range.by_ref().count(), 3);
+        assert!(range.is_empty());
+        assert_eq!(range.next(), None);
+
+        let bytes = range.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [3, 3]);
+        let decoded = RangeInclusive::<u8>::decode_from_bytes(&bytes).unwrap();
+        assert!(!decoded.is_empty());
+        assert_eq!(decoded, 3..=3);
+        assert!(decoded.eq([3]));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct SignedLen {
+        #[bin(len = "i32")]
+        items: Vec<u8>,
+    }
+
+    #[test]
+    fn signed_length_round_trips() {
+        let value = SignedLen {
+            items: vec![1, 2, 3],
+        };
+        let bytes = value.encode_to_bytes().unwrap();
+        assert_eq!(&bytes[..4], &3i32.to_le_bytes());
+        assert_eq!(SignedLen::decode_from_bytes(&bytes).unwrap(), value);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Tags {
+        #[bin(len = "u8")]
+        names: Vec<String>,
+    }
+
+    #[test]
+    fn length_override_only_applies_to_the_outer_prefix() {
+        let value = Tags {
+            names: vec!["a".repeat(300), "b".into()],
+        };
+        let bytes = value.encode_to_bytes().unwrap();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(&bytes[1..5], &300u32.to_le_bytes());
+        assert_eq!(bytes.len(), 1 + 4 + 300 + 4 + 1);
+        assert_eq!(Tags::decode_from_bytes(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn negative_signed_length_errors() {
+        let bytes = (-1i32).encode_to_bytes().unwrap();
+        let err = SignedLen::decode_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), Error::Custom(message) if message == "Negative length"));
+    }
+
+    #[test]
+    fn map_entries_stream_without_building_a_map() {
+        let map: HashMap<String, u32> = (1..=100).map(|i| (i.to_string(), i)).collect();
+        let bytes = map.encode_to_bytes().unwrap();
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        let mut sum = 0;
+        for entry in decoder.decode_map_entries::<String, u32>().unwrap() {
+            let (key, value) = entry.unwrap();
+            assert_eq!(key, value.to_string());
+            sum += value;
+        }
+        assert_eq!(sum, 5050);
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Unit;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Header {
+        version: u16,
+    }
+
+    #[test]
+    fn empty_input_decodes_zero_size_types() {
+        assert_eq!(Unit::decode_from_bytes(&[]).unwrap(), Unit);
+    }
+
+    #[test]
+    fn empty_input_fails_up_front_for_sized_types() {
+        let err = Header::decode_from_bytes(&[]).unwrap_err();
+        assert!(matches!(err, Error::EmptyInput));
+    }
+
+    #[test]
+    fn string_array_round_trips() {
+        let names = ["a".to_string(), "bc".to_string(), "def".to_string()];
+        let bytes = names.encode_to_bytes().unwrap();
+        assert_eq!(<[String; 3]>::decode_from_bytes(&bytes).unwrap(), names);
+    }
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl Decode for Counted {
+        const MIN_SIZE: usize = 1;
+
+        fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+            match decoder.decode_u8()? {
+                0 => Ok(Counted),
+                _ => Err("Invalid counted value".into()),
+            }
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn partial_array_failure_drops_decoded_elements() {
+        assert!(<[Counted; 4]>::decode_from_bytes(&[0, 0, 1, 0]).is_err());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn out_of_order_keys_are_lenient_by_default() {
+        let bytes = vec![(2u8, 'b'), (1u8, 'a')].encode_to_bytes().unwrap();
+        let map = BTreeMap::<u8, char>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(map, BTreeMap::from([(1, 'a'), (2, 'b')]));
+    }
+
+    #[test]
+    fn out_of_order_keys_error_when_verified() {
+        let ctx = Context::new(Endianness::Little).with_verify_sorted(true);
+
+        let bytes = vec![(2u8, 'b'), (1u8, 'a')].encode_to_bytes().unwrap();
+        let err = BTreeMap::<u8, char>::decode_with_ctx(&bytes, ctx).unwrap_err();
+        assert_eq!(err.to_string(), "Keys out of order");
+
+        let bytes = vec![3u16, 3].encode_to_bytes().unwrap();
+        assert!(BTreeSet::<u16>::decode_with_ctx(&bytes, ctx).is_err());
+
+        let bytes = vec![1u16, 3].encode_to_bytes().unwrap();
+        assert!(BTreeSet::<u16>::decode_with_ctx(&bytes, ctx).is_ok());
+    }
+
+    #[derive(Debug, Clone, PartialEq, Encode, Decode)]
+    struct Vertex {
+        x: u32,
+        y: u32,
+        z: u32,
+    }
+
+    #[test]
+    fn fixed_size_elements_round_trip() {
+        assert_eq!(Vertex::FIXED_SIZE, Some(12));
+        let vertices: Vec<_> = (0..100_000)
+            .map(|i| Vertex {
+                x: i,
+                y: i + 1,
+                z: i + 2,
+            })
+            .collect();
+        let bytes = vertices.encode_to_bytes().unwrap();
+        assert_eq!(bytes.len(), 4 + 12 * vertices.len());
+        assert_eq!(Vec::<Vertex>::decode_from_bytes(&bytes).unwrap(), vertices);
+    }
+
+    #[test]
+    fn truncated_fixed_size_elements_fail_before_allocating() {
+        let mut bytes = 100_000u32.encode_to_bytes().unwrap();
+        bytes.extend_from_slice(&[0; 12]);
+
+        let err = Vec::<Vertex>::decode_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), Error::UnexpectedEof { .. }));
+
+        let mut decoder = AllocBudget::new(BufferDecoder::new(&bytes), 1024);
+        let err = Vec::<Vertex>::decode(&mut decoder).unwrap_err();
+        assert!(matches!(err.kind(), Error::UnexpectedEof { .. }));
+        assert_eq!(decoder.allocated(), 0);
+    }
+
+    #[test]
+    fn fixed_size_elements_fire_field_hooks() {
+        let vertices = vec![Vertex { x: 1, y: 2, z: 3 }; 2];
+        let bytes = vertices.encode_to_bytes().unwrap();
+        let mut fields = Vec::new();
+        let mut decoder = InstrumentedCodec::new(BufferDecoder::new(&bytes))
+            .on_decode_field(|name, len| fields.push((name.to_string(), len)));
+        assert_eq!(Vec::<Vertex>::decode(&mut decoder).unwrap(), vertices);
+        drop(decoder);
+        let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["x", "y", "z", "x", "y", "z"]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct NonZero(u16);
+
+    impl Decode for NonZero {
+        const MIN_SIZE: usize = 2;
+        const FIXED_SIZE: Option<usize> = Some(2);
+
+        fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+            let start = decoder.position();
+            match decoder.decode_u16()? {
+                0 => Err(Error::at(start, "Zero")),
+                value => Ok(NonZero(value)),
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_size_element_errors_report_absolute_offsets() {
+        let bytes = vec![1u16, 2, 0].encode_to_bytes().unwrap();
+        let err = Vec::<NonZero>::decode_from_bytes(&bytes).unwrap_err();
+        assert_eq!(err.position(), Some(8));
+    }
+
+    #[test]
+    fn short_strings_are_copied_below_the_borrow_threshold() {
+        let bytes = ("ab", "abcdefgh").encode_to_bytes().unwrap();
+        let ctx = Context::new(Endianness::Little).with_borrow_threshold(4);
+        let mut decoder = BufferDecoder::with_ctx(&bytes, ctx);
+
+        let short = decoder.decode_cow_str().unwrap();
+        assert!(matches!(short, Cow::Owned(ref text) if text == "ab"));
+        let long = decoder.decode_cow_str().unwrap();
+        assert!(matches!(long, Cow::Borrowed("abcdefgh")));
+    }
+
+    #[test]
+    fn progress_increases_as_fields_are_decoded() {
+        let bytes = (7u32, "progress".to_string(), vec![1u8, 2, 3], 9u64)
+            .encode_to_bytes()
+            .unwrap();
+        let mut decoder = BufferDecoder::new(&bytes);
+        let mut samples = vec![decoder.progress().unwrap()];
+        u32::decode(&mut decoder).unwrap();
+        samples.push(decoder.progress().unwrap());
+        String::decode(&mut decoder).unwrap();
+        samples.push(decoder.progress().unwrap());
+        Vec::<u8>::decode(&mut decoder).unwrap();
+        samples.push(decoder.progress().unwrap());
+        u64::decode(&mut decoder).unwrap();
+        samples.push(decoder.progress().unwrap());
+
+        assert_eq!(samples.first(), Some(&0.0));
+        assert_eq!(samples.last(), Some(&1.0));
+        assert!(samples.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ReadDecoder::new(bytes.as_slice()).progress(), None);
+    }
+
+    struct Dictionary<'a>(&'a [&'a str]);
+
+    impl<'a> DecodeSeed for Dictionary<'a> {
+        type Value = &'a str;
+
+        fn decode_seed<D: Decoder>(self, decoder: &mut D) -> Result<Self::Value, Error> {
+            let index = decoder.decode_u32()? as usize;
+            self.0
+                .get(index)
+                .copied()
+                .ok_or_else(|| "Unknown dictionary index".into())
+        }
+    }
+
+    #[test]
+    fn seed_resolves_indices_against_a_caller_table() {
+        let table = ["red", "green", "blue"];
+        let bytes = (2u32, 0u32, 7u32).encode_to_bytes().unwrap();
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(
+            Dictionary(&table).decode_seed(&mut decoder).unwrap(),
+            "blue"
+        );
+        assert_eq!(Dictionary(&table).decode_seed(&mut decoder).unwrap(), "red");
+        assert!(Dictionary(&table).decode_seed(&mut decoder).is_err());
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(PhantomData::<u32>.decode_seed(&mut decoder).unwrap(), 2);
+    }
+
+    #[test]
+    fn string_types_share_one_wire_format() {
+        let text = "wire contract";
+        let encodings = [
+            text.to_string().encode_to_bytes().unwrap(),
+            text.encode_to_bytes().unwrap(),
+            Box::<str>::from(text).encode_to_bytes().unwrap(),
+            Cow::Borrowed(text).encode_to_bytes().unwrap(),
+            Rc::<str>::from(text).encode_to_bytes().unwrap(),
+            Arc::<str>::from(text).encode_to_bytes().unwrap(),
+        ];
+        assert!(encodings.iter().all(|bytes| *bytes == encodings[0]));
+
+        for bytes in &encodings {
+            assert_eq!(String::decode_from_bytes(bytes).unwrap(), text);
+            assert_eq!(<&str>::decode_from_borrowed_bytes(bytes).unwrap(), text);
+            assert_eq!(&*Box::<str>::decode_from_bytes(bytes).unwrap(), text);
+            assert_eq!(Cow::<str>::decode_from_bytes(bytes).unwrap(), text);
+            assert_eq!(&*Rc::<str>::decode_from_bytes(bytes).unwrap(), text);
+            assert_eq!(&*Arc::<str>::decode_from_bytes(bytes).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn fixed_length_strings_round_trip_without_a_prefix() {
+        let mut encoder = BufferEncoder::new();
+        encoder.encode_string_fixed("abc\0", 4).unwrap();
+        encoder.encode_string_fixed("héllo", 6).unwrap();
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes.len(), 10);
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(decoder.decode_string_fixed(4).unwrap(), "abc\0");
+        assert_eq!(decoder.decode_string_fixed(6).unwrap(), "héllo");
+        assert!(decoder.decode_string_fixed(1).is_err());
+    }
+
+    #[test]
+    fn fixed_length_string_rejects_the_wrong_length() {
+        let mut encoder = BufferEncoder::new();
+        let err = encoder.encode_string_fixed("abc", 4).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid string length");
+        assert!(encoder.as_bytes().is_empty());
+
+        let bytes = [0xff, 0xfe];
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert!(decoder.decode_string_fixed(2).is_err());
+    }
+
+    #[test]
+    fn owned_buffer_decodes_from_a_reader() {
+        let bytes = (42u32, "cursor".to_string()).encode_to_bytes().unwrap();
+        let ctx = Context::new(Endianness::Little);
+        let mut decoder = OwnedBufferDecoder::from_reader(Cursor::new(bytes), ctx).unwrap();
+        assert_eq!(u32::decode(&mut decoder).unwrap(), 42);
+        assert_eq!(String::decode(&mut decoder).unwrap(), "cursor");
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[test]
+    fn explicit_endian_methods_ignore_the_context() {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let mut encoder = BufferEncoder::with_ctx(Context::new(endianness));
+            encoder.encode_u32_le(0x0102_0304).unwrap();
+            encoder.encode_u32_be(0x0102_0304).unwrap();
+            encoder.encode_i16_be(-2).unwrap();
+            encoder.encode_f64_le(1.5).unwrap();
+            let bytes = encoder.into_bytes();
+            assert_eq!(bytes[..8], [4, 3, 2, 1, 1, 2, 3, 4]);
+            assert_eq!(bytes[8..10], [0xff, 0xfe]);
+            assert_eq!(bytes[10..], 1.5f64.to_le_bytes());
+
+            let mut decoder = BufferDecoder::with_ctx(&bytes, Context::new(endianness));
+            assert_eq!(decoder.decode_u32_le().unwrap(), 0x0102_0304);
+            assert_eq!(decoder.decode_u32_be().unwrap(), 0x0102_0304);
+            assert_eq!(decoder.decode_i16_be().unwrap(), -2);
+            assert_eq!(decoder.decode_f64_le().unwrap(), 1.5);
+        }
+    }
+
+    #[test]
+    fn expect_padding_checks_every_byte() {
+        let mut encoder = BufferEncoder::new();
+        encoder.encode_u8(7).unwrap();
+        encoder.pad(3, 0xaa).unwrap();
+        encoder.encode_u8(9).unwrap();
+        let mut bytes = encoder.into_bytes();
+        assert_eq!(bytes, [7, 0xaa, 0xaa, 0xaa, 9]);
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(decoder.decode_u8().unwrap(), 7);
+        decoder.expect_padding(3, 0xaa).unwrap();
+        assert_eq!(decoder.decode_u8().unwrap(), 9);
+
+        bytes[2] = 0xab;
+        let mut decoder = BufferDecoder::new(&bytes);
+        decoder.decode_u8().unwrap();
+        let err = decoder.expect_padding(3, 0xaa).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid padding");
+    }
+}
@@ -1,20 +1,96 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+    ffi::{CStr, CString},
+    io::Write,
+    num::{
+        NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroU8, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU128, Saturating, Wrapping,
+    },
+    ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicBool, AtomicI8, AtomicI16, AtomicI32, AtomicI64, AtomicU8, AtomicU16, AtomicU32,
+            AtomicU64, AtomicUsize, Ordering,
+        },
+    },
+};
+
+use crate::{
+    context::{Context, LenPrefix},
+    converter::ByteConvertable,
+    endian::Endianness,
+    error::Error,
+    hex,
+    shared::EncodeTable,
+    varint,
+};
+
+macro_rules! impl_endian_encoders {
+    ($($ty:ty => $le:ident, $be:ident), *) => {
+        $(
+            fn $le(&mut self, value: $ty) -> Result<(), Error> {
+                self.encode_endian(value, Endianness::Little)
+            }
 
-use crate::{context::Context, converter::ByteConvertable, endian::Endianness, error::Error};
+            fn $be(&mut self, value: $ty) -> Result<(), Error> {
+                self.encode_endian(value, Endianness::Big)
+            }
+        )*
+    };
+}
 
 pub trait Encoder {
     fn context(&self) -> Context;
 
+    fn set_context(&mut self, context: Context);
+
     fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error>;
 
+    fn position(&self) -> usize;
+
+    fn begin_field(&mut self, _name: &str) {}
+
+    fn end_field(&mut self, _name: &str) {}
+
+    fn shared_table(&mut self) -> Option<&mut EncodeTable> {
+        None
+    }
+
     fn encode<T, const N: usize>(&mut self, value: T) -> Result<(), Error>
     where
         T: ByteConvertable<N>,
     {
-        let bytes = self.context().endian.to_bytes(value);
+        self.encode_endian(value, self.context().endian)
+    }
+
+    fn encode_endian<T, const N: usize>(
+        &mut self,
+        value: T,
+        endian: Endianness,
+    ) -> Result<(), Error>
+    where
+        T: ByteConvertable<N>,
+    {
+        let bytes = endian.to_bytes(value);
         self.encode_bytes(bytes.as_ref())
     }
 
+    impl_endian_encoders!(
+        i16 => encode_i16_le, encode_i16_be,
+        u16 => encode_u16_le, encode_u16_be,
+        i32 => encode_i32_le, encode_i32_be,
+        u32 => encode_u32_le, encode_u32_be,
+        i64 => encode_i64_le, encode_i64_be,
+        u64 => encode_u64_le, encode_u64_be,
+        i128 => encode_i128_le, encode_i128_be,
+        u128 => encode_u128_le, encode_u128_be,
+        f32 => encode_f32_le, encode_f32_be,
+        f64 => encode_f64_le, encode_f64_be
+    );
+
     fn encode_i8(&mut self, value: i8) -> Result<(), Error> {
         self.encode_bytes(&[value as u8])
     }
@@ -56,10 +132,16 @@ pub trait Encoder {
     }
 
     fn encode_f32(&mut self, value: f32) -> Result<(), Error> {
+        if self.context().canonical_floats {
+            return self.encode(canonical_f32(value));
+        }
         self.encode(value)
     }
 
     fn encode_f64(&mut self, value: f64) -> Result<(), Error> {
+        if self.context().canonical_floats {
+            return self.encode(canonical_f64(value));
+        }
         self.encode(value)
     }
 
@@ -67,10 +149,96 @@ pub trait Encoder {
         self.encode_bytes(&[value as u8])
     }
 
+    fn encode_len(&mut self, len: usize) -> Result<(), Error> {
+        let mut context = self.context();
+        let prefix = context.take_len();
+        self.set_context(context);
+        match prefix {
+            LenPrefix::U8 => self.encode_u8(u8::try_from(len).map_err(|_| "Length out of range")?),
+            LenPrefix::U16 => {
+                self.encode_u16(u16::try_from(len).map_err(|_| "Length out of range")?)
+            }
+            LenPrefix::U32 => {
+                self.encode_u32(u32::try_from(len).map_err(|_| "Length out of range")?)
+            }
+            LenPrefix::I32 => {
+                self.encode_i32(i32::try_from(len).map_err(|_| "Length out of range")?)
+            }
+            LenPrefix::I64 => {
+                self.encode_i64(i64::try_from(len).map_err(|_| "Length out of range")?)
+            }
+            LenPrefix::U64 => self.encode_u64(len as u64),
+            LenPrefix::Varint => varint::encode_varint_u64(self, len as u64),
+        }
+    }
+
     fn encode_string(&mut self, value: &str) -> Result<(), Error> {
-        self.encode(value.len() as u32)?;
+        self.encode_len(value.len())?;
         self.encode_bytes(value.as_bytes())
     }
+
+    fn write_version_header(&mut self, version: u16) -> Result<(), Error> {
+        self.encode_u16(version)?;
+        self.set_context(self.context().with_stream_version(version));
+        Ok(())
+    }
+
+    fn encode_cstr(&mut self, value: &str) -> Result<(), Error> {
+        if value.contains('\0') {
+            return Err("Interior NUL in C string".into());
+        }
+        self.encode_bytes(value.as_bytes())?;
+        self.encode_u8(0)
+    }
+
+    fn pad(&mut self, len: usize, fill: u8) -> Result<(), Error> {
+        self.encode_bytes(&vec![fill; len])
+    }
+
+    fn align_to(&mut self, alignment: usize) -> Result<(), Error> {
+        if alignment == 0 {
+            return Err("Alignment must be non-zero".into());
+        }
+        match self.position() % alignment {
+            0 => Ok(()),
+            rem => self.pad(alignment - rem, 0),
+        }
+    }
+
+    fn encode_string_fixed(&mut self, value: &str, len: usize) -> Result<(), Error> {
+        if value.len() != len {
+            return Err("Invalid string length".into());
+        }
+        self.encode_bytes(value.as_bytes())
+    }
+
+    fn encode_string_padded(&mut self, value: &str, width: usize) -> Result<(), Error> {
+        if value.len() > width {
+            return Err("String exceeds fixed width".into());
+        }
+        self.encode_bytes(value.as_bytes())?;
+        self.pad(width - value.len(), 0)
+    }
+}
+
+fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
 }
 
 pub struct BufferEncoder {
@@ -83,8 +251,23 @@ impl Encoder for BufferEncoder {
         self.context
     }
 
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
     fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
-        Ok(self.buffer.extend_from_slice(slice))
+        self.buffer.extend_from_slice(slice);
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl Default for BufferEncoder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -103,11 +286,122 @@ impl BufferEncoder {
         }
     }
 
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_ctx(capacity, Context::new(Endianness::Little))
+    }
+
+    pub fn with_capacity_and_ctx(capacity: usize, context: Context) -> Self {
+        Self {
+            buffer: Vec::with_capacity(capacity),
+            context,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
     pub fn into_bytes(self) -> Vec<u8> {
         self.buffer
     }
 }
 
+pub struct WriteEncoder<W: Write> {
+    writer: W,
+    context: Context,
+    position: usize,
+}
+
+impl<W: Write> Encoder for WriteEncoder<W> {
+    fn context(&self) -> Context {
+        self.context
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(slice)?;
+        self.position += slice.len();
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<W: Write> WriteEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_ctx(writer, Context::new(Endianness::Little))
+    }
+
+    pub fn with_ctx(writer: W, context: Context) -> Self {
+        Self {
+            writer,
+            context,
+            position: 0,
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct CountingEncoder {
+    count: usize,
+    context: Context,
+}
+
+impl Encoder for CountingEncoder {
+    fn context(&self) -> Context {
+        self.context
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.count += slice.len();
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.count
+    }
+}
+
+impl Default for CountingEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CountingEncoder {
+    pub fn new() -> Self {
+        Self::with_ctx(Context::new(Endianness::Little))
+    }
+
+    pub fn with_ctx(context: Context) -> Self {
+        Self { count: 0, context }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
 pub trait Encode {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error>;
 
@@ -117,11 +411,45 @@ pub trait Encode {
         Ok(encoder.into_bytes())
     }
 
+    fn encode_to_bytes_with_capacity(&self, capacity: usize) -> Result<Vec<u8>, Error> {
+        let mut encoder = BufferEncoder::with_capacity(capacity);
+        self.encode(&mut encoder)?;
+        Ok(encoder.into_bytes())
+    }
+
     fn encode_with_ctx(&self, ctx: Context) -> Result<Vec<u8>, Error> {
         let mut encoder = BufferEncoder::with_ctx(ctx);
         self.encode(&mut encoder)?;
         Ok(encoder.into_bytes())
     }
+
+    fn encoded_size(&self) -> Result<usize, Error> {
+        self.encoded_size_with_ctx(Context::new(Endianness::Little))
+    }
+
+    fn encoded_size_with_ctx(&self, ctx: Context) -> Result<usize, Error> {
+        let mut encoder = CountingEncoder::with_ctx(ctx);
+        self.encode(&mut encoder)?;
+        Ok(encoder.count())
+    }
+
+    fn encode_canonical(&self) -> Result<Vec<u8>, Error> {
+        self.encode_with_ctx(Context::new(Endianness::Little).with_canonical(true))
+    }
+
+    fn encode_to_hex(&self) -> Result<String, Error> {
+        Ok(hex::to_hex(&self.encode_to_bytes()?))
+    }
+
+    #[cfg(feature = "base64")]
+    fn encode_to_base64(&self, alphabet: crate::base64::Alphabet) -> Result<String, Error> {
+        Ok(crate::base64::to_base64(&self.encode_to_bytes()?, alphabet))
+    }
+
+    #[cfg(feature = "crc32")]
+    fn encode_to_bytes_checked(&self) -> Result<Vec<u8>, Error> {
+        Ok(crate::crc32::append_checksum(self.encode_to_bytes()?))
+    }
 }
 
 impl Encode for i8 {
@@ -208,6 +536,68 @@ impl Encode for bool {
     }
 }
 
+macro_rules! impl_nonzero_encode {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+                    self.get().encode(encoder)
+                }
+            }
+        )*
+    };
+}
+
+impl_nonzero_encode!(
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128
+);
+
+macro_rules! impl_atomic_encode {
+    ($($ty:ty => $inner:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+                    self.load(Ordering::SeqCst).encode(encoder)
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_encode!(
+    AtomicBool => bool,
+    AtomicU8 => u8,
+    AtomicU16 => u16,
+    AtomicU32 => u32,
+    AtomicU64 => u64,
+    AtomicUsize => usize,
+    AtomicI8 => i8,
+    AtomicI16 => i16,
+    AtomicI32 => i32,
+    AtomicI64 => i64
+);
+
+impl<T: Encode> Encode for Wrapping<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.0.encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for Saturating<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.0.encode(encoder)
+    }
+}
+
 impl Encode for char {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
         encoder.encode_u32(*self as u32)
@@ -226,21 +616,64 @@ impl Encode for str {
     }
 }
 
-impl<T: Encode> Encode for (T, T) {
+impl Encode for CStr {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_bytes(self.to_bytes_with_nul())
+    }
+}
+
+impl Encode for CString {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        self.0.encode(encoder)?;
-        self.1.encode(encoder)
+        self.as_c_str().encode(encoder)
     }
 }
 
-impl<T: Encode> Encode for (T, T, T) {
+impl<B: Encode + ToOwned + ?Sized> Encode for Cow<'_, B> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        self.0.encode(encoder)?;
-        self.1.encode(encoder)?;
-        self.2.encode(encoder)
+        (**self).encode(encoder)
+    }
+}
+
+// Borrowed values share the wire format of their owned counterparts: bytes
+// written from `(&str, &[u8], u32)` decode as `(String, Vec<u8>, u32)`.
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        (**self).encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_len(self.len())?;
+        for item in self {
+            item.encode(encoder)?;
+        }
+        Ok(())
     }
 }
 
+macro_rules! impl_tuple_encode {
+    ($(($($name:ident $index:tt),+)),*) => {
+        $(
+            impl<$($name: Encode),+> Encode for ($($name,)+) {
+                fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+                    $(self.$index.encode(encoder)?;)+
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple_encode!(
+    (T0 0),
+    (T0 0, T1 1),
+    (T0 0, T1 1, T2 2),
+    (T0 0, T1 1, T2 2, T3 3),
+    (T0 0, T1 1, T2 2, T3 3, T4 4),
+    (T0 0, T1 1, T2 2, T3 3, T4 4, T5 5)
+);
+
 impl<T: Encode> Encode for Option<T> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
         match self {
@@ -268,9 +701,71 @@ impl<T: Encode, Er: Encode> Encode for Result<T, Er> {
     }
 }
 
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        for item in self {
+            item.encode(encoder)?;
+        }
+        Ok(())
+    }
+}
+
 impl<T: Encode> Encode for Vec<T> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        encoder.encode_u32(self.len() as u32)?;
+        self.as_slice().encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for VecDeque<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_len(self.len())?;
+        for item in self {
+            item.encode(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_canonical_entries<E, T>(
+    encoder: &mut E,
+    len: usize,
+    entries: impl Iterator<Item = T>,
+) -> Result<(), Error>
+where
+    E: Encoder,
+    T: Encode,
+{
+    // Entries are ordered by their standalone encoding but written through the
+    // caller's encoder, so shared tables and hooks see them in output order.
+    encoder.encode_len(len)?;
+    let context = encoder.context();
+    let mut entries = entries
+        .map(|entry| Ok((entry.encode_with_ctx(context)?, entry)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (_, entry) in entries {
+        entry.encode(encoder)?;
+    }
+    Ok(())
+}
+
+impl<T: Encode> Encode for LinkedList<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_len(self.len())?;
+        for item in self {
+            item.encode(encoder)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Encode> Encode for BinaryHeap<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        if encoder.context().canonical {
+            return encode_canonical_entries(encoder, self.len(), self.iter());
+        }
+        encoder.encode_len(self.len())?;
         for item in self {
             item.encode(encoder)?;
         }
@@ -280,7 +775,10 @@ impl<T: Encode> Encode for Vec<T> {
 
 impl<T: Encode> Encode for HashSet<T> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        encoder.encode_u32(self.len() as u32)?;
+        if encoder.context().canonical {
+            return encode_canonical_entries(encoder, self.len(), self.iter());
+        }
+        encoder.encode_len(self.len())?;
         for item in self {
             item.encode(encoder)?;
         }
@@ -290,7 +788,7 @@ impl<T: Encode> Encode for HashSet<T> {
 
 impl<T: Encode> Encode for BTreeSet<T> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        encoder.encode_u32(self.len() as u32)?;
+        encoder.encode_len(self.len())?;
         for item in self {
             item.encode(encoder)?;
         }
@@ -300,7 +798,10 @@ impl<T: Encode> Encode for BTreeSet<T> {
 
 impl<K: Encode, V: Encode> Encode for HashMap<K, V> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        encoder.encode_u32(self.len() as u32)?;
+        if encoder.context().canonical {
+            return encode_canonical_entries(encoder, self.len(), self.iter());
+        }
+        encoder.encode_len(self.len())?;
         for (key, value) in self {
             key.encode(encoder)?;
             value.encode(encoder)?;
@@ -311,7 +812,7 @@ impl<K: Encode, V: Encode> Encode for HashMap<K, V> {
 
 impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
     fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
-        encoder.encode_u32(self.len() as u32)?;
+        encoder.encode_len(self.len())?;
         for (key, value) in self {
             key.encode(encoder)?;
             value.encode(encoder)?;
@@ -319,3 +820,152 @@ impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
         Ok(())
     }
 }
+
+impl<T: Encode> Encode for Range<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.start.encode(encoder)?;
+        self.end.encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for RangeFrom<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.start.encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for RangeTo<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.end.encode(encoder)
+    }
+}
+
+// Only the bounds are written; an exhausted range decodes as a fresh `start..=end`.
+// Only the bounds are written. The flag an iterated-out range carries is not
+// part of the format, so an exhausted range decodes as a fresh `start..=end`.
+impl<T: Encode> Encode for RangeInclusive<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.start().encode(encoder)?;
+        self.end().encode(encoder)
+    }
+}
+
+impl Encode for RangeFull {
+    fn encode<E: Encoder>(&self, _encoder: &mut E) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        (**self).encode(encoder)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Rc<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        (**self).encode(encoder)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Arc<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        (**self).encode(encoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, VecDeque},
+        ffi::{CStr, CString},
+    };
+
+    use crate::{context::Context, decoder::Decode, encoder::Encode, endian::Endianness};
+
+    #[test]
+    fn borrowed_tuple_decodes_as_owned_tuple() {
+        let borrowed: (&str, &[u8], u32) = ("binrs", &[1, 2, 3], 7);
+        let owned = (String::from("binrs"), vec![1u8, 2, 3], 7u32);
+
+        let bytes = borrowed.encode_to_bytes().unwrap();
+        assert_eq!(bytes, owned.encode_to_bytes().unwrap());
+        assert_eq!(
+            <(String, Vec<u8>, u32)>::decode_from_bytes(&bytes).unwrap(),
+            owned
+        );
+    }
+
+    #[test]
+    fn borrowed_c_str_decodes_as_owned_c_string() {
+        let borrowed: &CStr = c"binrs";
+        let bytes = (borrowed, 7u8).encode_to_bytes().unwrap();
+        assert_eq!(bytes, b"binrs\0\x07");
+        assert_eq!(
+            bytes[..6],
+            *CString::from(borrowed).encode_to_bytes().unwrap()
+        );
+
+        let (owned, tail) = <(CString, u8)>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(owned.as_c_str(), borrowed);
+        assert_eq!(tail, 7);
+    }
+
+    #[test]
+    fn equal_hash_maps_encode_canonically_identical() {
+        let forward: HashMap<u32, String> = (0..64).map(|i| (i, i.to_string())).collect();
+        let mut backward = HashMap::new();
+        for i in (0..64).rev() {
+            backward.insert(i, i.to_string());
+        }
+        assert_eq!(forward, backward);
+
+        let bytes = forward.encode_canonical().unwrap();
+        assert_eq!(bytes, backward.encode_canonical().unwrap());
+        assert_eq!(HashMap::decode_from_bytes(&bytes).unwrap(), forward);
+    }
+
+    #[test]
+    fn canonical_floats_collapse_nan_payloads() {
+        let quiet = f32::from_bits(0x7fc0_0001);
+        let negative = f32::from_bits(0xffc0_0002);
+        let wide = f64::from_bits(0x7ff8_0000_0000_0003);
+        let canonical = Context::new(Endianness::Little).with_canonical_floats(true);
+
+        let bytes = (quiet, negative, wide).encode_with_ctx(canonical).unwrap();
+        assert_eq!(
+            bytes,
+            (f32::NAN, f32::NAN, f64::NAN)
+                .encode_with_ctx(canonical)
+                .unwrap()
+        );
+        assert_eq!(bytes[..4], f32::NAN.to_bits().to_le_bytes());
+        assert_eq!(bytes[4..8], f32::NAN.to_bits().to_le_bytes());
+        assert_eq!(bytes[8..], f64::NAN.to_bits().to_le_bytes());
+
+        let raw = (quiet, negative, wide).encode_to_bytes().unwrap();
+        assert_eq!(raw[..4], 0x7fc0_0001u32.to_le_bytes());
+        assert_eq!(raw[4..8], 0xffc0_0002u32.to_le_bytes());
+        assert_eq!(raw[8..], 0x7ff8_0000_0000_0003u64.to_le_bytes());
+    }
+
+    #[test]
+    fn rotated_deque_keeps_front_to_back_order() {
+        let mut deque = VecDeque::with_capacity(8);
+        deque.extend([1u16, 2, 3, 4, 5, 6]);
+        deque.drain(..4);
+        deque.extend([7, 8, 9, 10]);
+        deque.push_front(0);
+        let (front, back) = deque.as_slices();
+        assert!(!front.is_empty() && !back.is_empty());
+
+        let bytes = deque.encode_to_bytes().unwrap();
+        assert_eq!(
+            bytes,
+            vec![0u16, 5, 6, 7, 8, 9, 10].encode_to_bytes().unwrap()
+        );
+        let decoded = VecDeque::<u16>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, deque);
+        assert!(decoded.iter().eq([0, 5, 6, 7, 8, 9, 10].iter()));
+    }
+}
@@ -1,9 +1,10 @@
-use crate::converter::ByteConvertable;
+use crate::{converter::ByteConvertable, error::Error};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Endianness {
     Little,
     Big,
+    Native,
 }
 
 impl Endianness {
@@ -14,6 +15,7 @@ impl Endianness {
         match self {
             Endianness::Little => value.to_le_bytes(),
             Endianness::Big => value.to_be_bytes(),
+            Endianness::Native => value.to_ne_bytes(),
         }
     }
 
@@ -24,6 +26,126 @@ impl Endianness {
         match self {
             Endianness::Little => T::from_le_bytes(bytes),
             Endianness::Big => T::from_be_bytes(bytes),
+            Endianness::Native => T::from_ne_bytes(bytes),
         }
     }
 }
+
+/// A field value that selects the byte order of the fields after it, as used
+/// by `#[bin(endian_field = "...")]`.
+pub trait EndianMarker {
+    fn endian(&self) -> Result<Endianness, Error>;
+}
+
+impl EndianMarker for Endianness {
+    fn endian(&self) -> Result<Endianness, Error> {
+        Ok(*self)
+    }
+}
+
+/// `0` for little-endian, `1` for big-endian.
+impl EndianMarker for u8 {
+    fn endian(&self) -> Result<Endianness, Error> {
+        match self {
+            0 => Ok(Endianness::Little),
+            1 => Ok(Endianness::Big),
+            _ => Err("Invalid byte order marker".into()),
+        }
+    }
+}
+
+/// `true` for big-endian.
+impl EndianMarker for bool {
+    fn endian(&self) -> Result<Endianness, Error> {
+        match self {
+            false => Ok(Endianness::Little),
+            true => Ok(Endianness::Big),
+        }
+    }
+}
+
+/// TIFF-style `II` for little-endian, `MM` for big-endian.
+impl EndianMarker for u16 {
+    fn endian(&self) -> Result<Endianness, Error> {
+        match self {
+            0x4949 => Ok(Endianness::Little),
+            0x4d4d => Ok(Endianness::Big),
+            _ => Err("Invalid byte order marker".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(endian_field = "order")]
+    struct Tiff {
+        order: u16,
+        version: u16,
+        offset: u32,
+    }
+
+    #[test]
+    fn marker_field_selects_the_byte_order_of_later_fields() {
+        let little = Tiff {
+            order: 0x4949,
+            version: 42,
+            offset: 8,
+        };
+        let bytes = little.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [0x49, 0x49, 42, 0, 8, 0, 0, 0]);
+        assert_eq!(Tiff::decode_from_bytes(&bytes).unwrap(), little);
+
+        let big = Tiff {
+            order: 0x4d4d,
+            ..little
+        };
+        let bytes = big.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [0x4d, 0x4d, 0, 42, 0, 0, 0, 8]);
+        assert_eq!(Tiff::decode_from_bytes(&bytes).unwrap(), big);
+    }
+
+    #[test]
+    fn invalid_marker_errors() {
+        let err = Tiff::decode_from_bytes(&[0, 0, 42, 0, 8, 0, 0, 0]).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid byte order marker");
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(endian_field = "big_endian")]
+    struct Flagged {
+        big_endian: bool,
+        value: u16,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(endian_field = "order")]
+    struct Tagged {
+        order: u8,
+        value: u16,
+    }
+
+    #[test]
+    fn bool_and_u8_markers_select_the_byte_order() {
+        for big_endian in [false, true] {
+            let value = Flagged {
+                big_endian,
+                value: 0x0102,
+            };
+            let bytes = value.encode_to_bytes().unwrap();
+            assert_eq!(bytes[1] == 0x01, big_endian);
+            assert_eq!(Flagged::decode_from_bytes(&bytes).unwrap(), value);
+
+            let value = Tagged {
+                order: big_endian as u8,
+                value: 0x0102,
+            };
+            let bytes = value.encode_to_bytes().unwrap();
+            assert_eq!(bytes[1] == 0x01, big_endian);
+            assert_eq!(Tagged::decode_from_bytes(&bytes).unwrap(), value);
+        }
+        assert!(Tagged::decode_from_bytes(&[2, 1, 2]).is_err());
+    }
+}
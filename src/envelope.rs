@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+    private::sum_fixed_sizes,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Envelope<T> {
+    pub request_id: u64,
+    pub payload: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(request_id: u64, payload: T) -> Self {
+        Self {
+            request_id,
+            payload,
+        }
+    }
+
+    pub fn reply<U>(&self, payload: U) -> Envelope<U> {
+        Envelope::new(self.request_id, payload)
+    }
+
+    pub fn into_payload(self) -> T {
+        self.payload
+    }
+}
+
+impl<T: Encode> Encode for Envelope<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_u64(self.request_id)?;
+        self.payload.encode(encoder)
+    }
+}
+
+impl<T: Decode> Decode for Envelope<T> {
+    const MIN_SIZE: usize = 8 + T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = sum_fixed_sizes(&[Some(8), T::FIXED_SIZE]);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let request_id = decoder.decode_u64()?;
+        let payload = T::decode(decoder)?;
+        Ok(Self::new(request_id, payload))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RequestIds {
+    next: AtomicU64,
+}
+
+impl RequestIds {
+    pub const fn new() -> Self {
+        Self::starting_at(0)
+    }
+
+    pub const fn starting_at(start: u64) -> Self {
+        Self {
+            next: AtomicU64::new(start),
+        }
+    }
+
+    pub fn next_id(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn wrap<T>(&self, payload: T) -> Envelope<T> {
+        Envelope::new(self.next_id(), payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Envelope, RequestIds};
+    use crate::prelude::*;
+
+    #[test]
+    fn envelope_round_trips_with_its_request_id() {
+        let envelope = Envelope::new(42, String::from("ping"));
+        let bytes = envelope.encode_to_bytes().unwrap();
+        assert_eq!(bytes[..8], 42u64.to_le_bytes());
+        assert_eq!(
+            Envelope::<String>::decode_from_bytes(&bytes).unwrap(),
+            envelope
+        );
+
+        let reply = envelope.reply(7u32);
+        assert_eq!(Envelope::<u32>::FIXED_SIZE, Some(12));
+        let bytes = reply.encode_to_bytes().unwrap();
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(
+            Envelope::<u32>::decode_from_bytes(&bytes).unwrap(),
+            Envelope::new(42, 7)
+        );
+    }
+
+    #[test]
+    fn request_ids_increase_monotonically() {
+        let ids = RequestIds::starting_at(10);
+        let wrapped: Vec<_> = (0..5).map(|i| ids.wrap(i)).collect();
+        let request_ids: Vec<_> = wrapped.iter().map(|envelope| envelope.request_id).collect();
+        assert_eq!(request_ids, [10, 11, 12, 13, 14]);
+        assert_eq!(ids.next_id(), 15);
+        assert_eq!(RequestIds::new().next_id(), 0);
+    }
+}
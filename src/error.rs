@@ -1,6 +1,113 @@
-pub type Error = Box<dyn std::error::Error>;
+use std::{
+    ffi::NulError,
+    fmt::{self, Display},
+    io,
+    str::Utf8Error,
+    string::FromUtf8Error,
+};
 
-// pub enum Error {
-//     InvalidLength,
-//     InvalidData
-// }
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedEof { needed: usize, remaining: usize },
+    EmptyInput,
+    InvalidUtf8,
+    InvalidTag,
+    InvalidEnumVariant(u32),
+    DepthLimitExceeded,
+    AllocationBudgetExceeded,
+    Io(io::Error),
+    Custom(String),
+    At { position: usize, error: Box<Error> },
+}
+
+impl Error {
+    pub fn at(position: usize, error: impl Into<Error>) -> Self {
+        match error.into() {
+            error @ Error::At { .. } => error,
+            error => Error::At {
+                position,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Error::At { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::At { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEof { needed, remaining } => write!(
+                f,
+                "Not enough bytes to decode: needed {needed}, {remaining} remaining"
+            ),
+            Error::EmptyInput => f.write_str("Empty input"),
+            Error::InvalidUtf8 => f.write_str("Invalid UTF-8"),
+            Error::InvalidTag => f.write_str("Invalid tag"),
+            Error::InvalidEnumVariant(tag) => write!(f, "Invalid enum variant {tag}"),
+            Error::DepthLimitExceeded => f.write_str("Max depth exceeded"),
+            Error::AllocationBudgetExceeded => f.write_str("Allocation budget exceeded"),
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+            Error::Custom(message) => f.write_str(message),
+            Error::At { position, error } => write!(f, "{error} at offset {position}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::At { error, .. } => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Custom(message.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Custom(message)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(_: Utf8Error) -> Self {
+        Error::InvalidUtf8
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(_: FromUtf8Error) -> Self {
+        Error::InvalidUtf8
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(err: NulError) -> Self {
+        Error::Custom(err.to_string())
+    }
+}
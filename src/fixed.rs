@@ -0,0 +1,84 @@
+use crate::{
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<const FRAC: u32, T = i32>(pub T);
+
+pub type Q16_16 = Fixed<16, i32>;
+pub type Q32_32 = Fixed<32, i64>;
+
+macro_rules! impl_fixed {
+    ($($ty:ty), *) => {
+        $(
+            impl<const FRAC: u32> Fixed<FRAC, $ty> {
+                const SCALE: f64 = (1u128 << FRAC) as f64;
+
+                pub fn from_raw(raw: $ty) -> Self {
+                    Self(raw)
+                }
+
+                pub fn raw(self) -> $ty {
+                    self.0
+                }
+
+                pub fn from_f64(value: f64) -> Self {
+                    Self((value * Self::SCALE).round() as $ty)
+                }
+
+                pub fn to_f64(self) -> f64 {
+                    self.0 as f64 / Self::SCALE
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed!(i32, i64);
+
+impl<const FRAC: u32, T: Encode> Encode for Fixed<FRAC, T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.0.encode(encoder)
+    }
+}
+
+impl<const FRAC: u32, T: Decode> Decode for Fixed<FRAC, T> {
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Self(T::decode(decoder)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Q16_16, Q32_32};
+    use crate::{decoder::Decode, encoder::Encode};
+
+    #[test]
+    fn q16_16_round_trips_as_raw_integer() {
+        let value = Q16_16::from_f64(1.5);
+        assert_eq!(value.raw(), 0x0001_8000);
+
+        let bytes = value.encode_to_bytes().unwrap();
+        assert_eq!(bytes, 0x0001_8000i32.to_le_bytes());
+        assert_eq!(Q16_16::decode_from_bytes(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn f64_conversion_rounds_to_nearest_step() {
+        for value in [0.0, 1.0, -1.0, 0.1, -123.456, 32767.99] {
+            let error = (Q16_16::from_f64(value).to_f64() - value).abs();
+            assert!(
+                error <= 0.5 / 65536.0,
+                "{value} converted with error {error}"
+            );
+        }
+
+        let value = Q32_32::from_f64(-98765.4321);
+        assert!((value.to_f64() + 98765.4321).abs() <= 0.5 / 4_294_967_296.0);
+    }
+}
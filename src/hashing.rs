@@ -0,0 +1,213 @@
+use std::hash::Hasher;
+
+use crate::{
+    context::Context,
+    decoder::Decoder,
+    encoder::Encoder,
+    error::Error,
+    shared::{DecodeTable, EncodeTable},
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub struct HashingEncoder<E, H = Fnv1a> {
+    inner: E,
+    hasher: H,
+}
+
+impl<E> HashingEncoder<E> {
+    pub fn new(inner: E) -> Self {
+        Self::with_hasher(inner, Fnv1a::default())
+    }
+}
+
+impl<E, H: Hasher> HashingEncoder<E, H> {
+    pub fn with_hasher(inner: E, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    pub fn finish_hash(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Encoder, H: Hasher> Encoder for HashingEncoder<E, H> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.inner.encode_bytes(slice)?;
+        self.hasher.write(slice);
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut EncodeTable> {
+        self.inner.shared_table()
+    }
+}
+
+pub struct HashingDecoder<D, H = Fnv1a> {
+    inner: D,
+    hasher: H,
+}
+
+impl<D> HashingDecoder<D> {
+    pub fn new(inner: D) -> Self {
+        Self::with_hasher(inner, Fnv1a::default())
+    }
+}
+
+impl<D, H: Hasher> HashingDecoder<D, H> {
+    pub fn with_hasher(inner: D, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    pub fn finish_hash(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Decoder, H: Hasher> Decoder for HashingDecoder<D, H> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        let bytes = self.inner.decode_bytes(len)?;
+        self.hasher.write(bytes);
+        Ok(bytes)
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        self.inner.peek_bytes(len)
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        let bytes = self.inner.decode_remaining()?;
+        self.hasher.write(bytes);
+        Ok(bytes)
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        self.inner.total_len()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut DecodeTable> {
+        self.inner.shared_table()
+    }
+
+    fn track_alloc(&mut self, bytes: usize) -> Result<(), Error> {
+        self.inner.track_alloc(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+
+    use super::{Fnv1a, HashingDecoder, HashingEncoder};
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Record {
+        id: u32,
+        name: String,
+        samples: Vec<u16>,
+        tail: Option<i64>,
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference_vectors() {
+        assert_eq!(Fnv1a::default().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hasher = Fnv1a::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn encoder_and_decoder_hash_the_same_stream() {
+        let record = Record {
+            id: 7,
+            name: "sensor".to_string(),
+            samples: vec![1, 2, 3, 500],
+            tail: Some(-1),
+        };
+
+        let mut encoder = HashingEncoder::new(BufferEncoder::new());
+        record.encode(&mut encoder).unwrap();
+        let encoded_hash = encoder.finish_hash();
+        let bytes = encoder.into_inner().into_bytes();
+
+        let mut decoder = HashingDecoder::new(BufferDecoder::new(&bytes));
+        assert_eq!(Record::decode(&mut decoder).unwrap(), record);
+        assert_eq!(decoder.finish_hash(), encoded_hash);
+
+        let mut hasher = Fnv1a::default();
+        hasher.write(&bytes);
+        assert_eq!(hasher.finish(), encoded_hash);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::error::Error;
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(DIGITS[(byte >> 4) as usize] as char);
+        hex.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    hex
+}
+
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("Odd hex length".into());
+    }
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| Ok((digit(pair[0])? << 4) | digit(pair[1])?))
+        .collect()
+}
+
+fn digit(c: u8) -> Result<u8, Error> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err("Invalid hex character".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, to_hex};
+    use crate::prelude::*;
+
+    #[test]
+    fn values_round_trip_through_hex() {
+        let value = (0xdead_beefu32, String::from("hi"), vec![0u8, 15, 255]);
+        let hex = value.encode_to_hex().unwrap();
+        assert_eq!(hex, to_hex(&value.encode_to_bytes().unwrap()));
+        assert!(hex.starts_with("efbeadde"));
+        assert_eq!(
+            <(u32, String, Vec<u8>)>::decode_from_hex(&hex).unwrap(),
+            value
+        );
+        assert_eq!(from_hex("DEADbeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+    }
+
+    #[test]
+    fn invalid_hex_is_rejected() {
+        let err = u8::decode_from_hex("abc").unwrap_err();
+        assert_eq!(err.to_string(), "Odd hex length");
+        let err = u16::decode_from_hex("0g").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid hex character");
+        assert_eq!(from_hex("").unwrap(), []);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    context::Context,
+    decoder::Decoder,
+    encoder::Encoder,
+    error::Error,
+    shared::{DecodeTable, EncodeTable},
+};
+
+type FieldHook<'a> = Box<dyn FnMut(&str, usize) + 'a>;
+
+pub struct InstrumentedCodec<'a, C> {
+    inner: C,
+    bytes: usize,
+    starts: Vec<usize>,
+    on_encode_field: Option<FieldHook<'a>>,
+    on_decode_field: Option<FieldHook<'a>>,
+}
+
+impl<'a, C> InstrumentedCodec<'a, C> {
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            starts: Vec::new(),
+            on_encode_field: None,
+            on_decode_field: None,
+        }
+    }
+
+    pub fn on_encode_field(mut self, hook: impl FnMut(&str, usize) + 'a) -> Self {
+        self.on_encode_field = Some(Box::new(hook));
+        self
+    }
+
+    pub fn on_decode_field(mut self, hook: impl FnMut(&str, usize) + 'a) -> Self {
+        self.on_decode_field = Some(Box::new(hook));
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn begin(&mut self) {
+        self.starts.push(self.bytes);
+    }
+
+    fn end(&mut self) -> usize {
+        let start = self.starts.pop().unwrap_or(self.bytes);
+        self.bytes - start
+    }
+}
+
+impl<C: Encoder> Encoder for InstrumentedCodec<'_, C> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.inner.encode_bytes(slice)?;
+        self.bytes += slice.len();
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+        self.begin();
+    }
+
+    fn end_field(&mut self, name: &str) {
+        let len = self.end();
+        if let Some(hook) = self.on_encode_field.as_mut() {
+            hook(name, len);
+        }
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut EncodeTable> {
+        self.inner.shared_table()
+    }
+}
+
+impl<C: Decoder> Decoder for InstrumentedCodec<'_, C> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        let bytes = self.inner.decode_bytes(len)?;
+        self.bytes += len;
+        Ok(bytes)
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        self.inner.peek_bytes(len)
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        let bytes = self.inner.decode_remaining()?;
+        self.bytes += bytes.len();
+        Ok(bytes)
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        self.inner.total_len()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+        self.begin();
+    }
+
+    fn end_field(&mut self, name: &str) {
+        let len = self.end();
+        if let Some(hook) = self.on_decode_field.as_mut() {
+            hook(name, len);
+        }
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut DecodeTable> {
+        self.inner.shared_table()
+    }
+
+    fn track_alloc(&mut self, bytes: usize) -> Result<(), Error> {
+        self.inner.track_alloc(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::InstrumentedCodec;
+    use crate::{
+        prelude::*,
+        shared::{Shared, SharedDecoder, SharedEncoder},
+    };
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct User {
+        id: u64,
+        username: String,
+        email: Option<String>,
+        roles: Vec<String>,
+    }
+
+    fn user() -> User {
+        User {
+            id: 1001,
+            username: "johndoe".to_string(),
+            email: None,
+            roles: vec!["admin".to_string()],
+        }
+    }
+
+    const FIELDS: [(&str, usize); 4] = [("id", 8), ("username", 11), ("email", 1), ("roles", 13)];
+
+    #[test]
+    fn encode_hook_fires_for_each_field_in_order() {
+        let mut fields = Vec::new();
+        let mut encoder = InstrumentedCodec::new(BufferEncoder::new())
+            .on_encode_field(|name, len| fields.push((name.to_string(), len)));
+        user().encode(&mut encoder).unwrap();
+        drop(encoder);
+
+        let expected: Vec<_> = FIELDS
+            .iter()
+            .map(|&(name, len)| (name.to_string(), len))
+            .collect();
+        assert_eq!(fields, expected);
+    }
+
+    #[test]
+    fn decode_hook_fires_for_each_field_in_order() {
+        let bytes = user().encode_to_bytes().unwrap();
+        let mut fields = Vec::new();
+        let mut decoder = InstrumentedCodec::new(BufferDecoder::new(&bytes))
+            .on_decode_field(|name, len| fields.push((name.to_string(), len)));
+        assert_eq!(User::decode(&mut decoder).unwrap(), user());
+        drop(decoder);
+
+        let expected: Vec<_> = FIELDS
+            .iter()
+            .map(|&(name, len)| (name.to_string(), len))
+            .collect();
+        assert_eq!(fields, expected);
+    }
+
+    #[test]
+    fn forwards_shared_tables() {
+        let name = Rc::new("shared".to_string());
+        let pair = (Shared(Rc::clone(&name)), Shared(name));
+
+        let mut plain = SharedEncoder::new(BufferEncoder::new());
+        pair.encode(&mut plain).unwrap();
+        let mut encoder = InstrumentedCodec::new(SharedEncoder::new(BufferEncoder::new()));
+        pair.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_inner().into_inner().into_bytes();
+        assert_eq!(bytes, plain.into_inner().into_bytes());
+
+        let mut decoder = InstrumentedCodec::new(SharedDecoder::new(BufferDecoder::new(&bytes)));
+        let (a, b) = <(Shared<Rc<String>>, Shared<Rc<String>>)>::decode(&mut decoder).unwrap();
+        assert!(Rc::ptr_eq(&a.0, &b.0));
+    }
+}
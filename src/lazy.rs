@@ -0,0 +1,180 @@
+use std::{borrow::Borrow, cell::OnceCell, collections::HashMap, hash::Hash};
+
+use crate::{
+    context::Context,
+    decoder::{BufferDecoder, Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+    private::encode_framed,
+};
+
+/// A map whose values are decoded on first access.
+///
+/// Each value is written with a u32 byte length so it can be located without
+/// decoding it. This makes the format incompatible with `HashMap` and
+/// `BTreeMap`: write it with [`LazyMap::encode_map`] and read it only as a
+/// `LazyMap`.
+pub struct LazyMap<'a, K, V> {
+    entries: HashMap<K, (&'a [u8], OnceCell<V>)>,
+    context: Context,
+}
+
+impl<'a, K, V> LazyMap<'a, K, V>
+where
+    K: Decode + Eq + Hash,
+    V: Decode,
+{
+    pub fn decode(decoder: &mut BufferDecoder<'a>) -> Result<Self, Error> {
+        let len = decoder.decode_len()?;
+        let mut entries = HashMap::with_capacity(decoder.context().preallocate(len));
+        for _ in 0..len {
+            let key = K::decode(decoder)?;
+            let size = decoder.decode_u32()? as usize;
+            let bytes = decoder.decode_borrowed_bytes(size)?;
+            entries.insert(key, (bytes, OnceCell::new()));
+        }
+        Ok(Self {
+            entries,
+            context: decoder.context(),
+        })
+    }
+
+    pub fn decode_from_bytes(bytes: &'a [u8]) -> Result<Self, Error> {
+        Self::decode(&mut BufferDecoder::new(bytes))
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Result<Option<&V>, Error>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let Some((bytes, cell)) = self.entries.get(key) else {
+            return Ok(None);
+        };
+        if cell.get().is_none() {
+            let mut decoder = BufferDecoder::with_ctx(bytes, self.context);
+            let value = V::decode(&mut decoder)?;
+            if decoder.remaining() > 0 {
+                return Err(Error::at(
+                    decoder.position(),
+                    "Lazy value has trailing bytes",
+                ));
+            }
+            let _ = cell.set(value);
+        }
+        Ok(cell.get())
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Encode, V> LazyMap<'_, K, V> {
+    pub fn encode_map<E: Encoder>(map: &HashMap<K, V>, encoder: &mut E) -> Result<(), Error>
+    where
+        V: Encode,
+    {
+        encoder.encode_len(map.len())?;
+        for (key, value) in map {
+            key.encode(encoder)?;
+            encode_framed(encoder, |frame| value.encode(frame))?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Encode, V> Encode for LazyMap<'_, K, V> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_len(self.entries.len())?;
+        for (key, (bytes, _)) in &self.entries {
+            key.encode(encoder)?;
+            encoder.encode_u32(u32::try_from(bytes.len()).map_err(|_| "Value too large")?)?;
+            encoder.encode_bytes(bytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::encoder::BufferEncoder;
+
+    static DECODED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl Encode for Counted {
+        fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+            self.0.encode(encoder)
+        }
+    }
+
+    impl Decode for Counted {
+        fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+            DECODED.fetch_add(1, Ordering::SeqCst);
+            Ok(Counted(u32::decode(decoder)?))
+        }
+    }
+
+    #[test]
+    fn values_are_decoded_on_first_get() {
+        let map: HashMap<u32, Counted> = (0..4).map(|i| (i, Counted(i * 10))).collect();
+        let mut encoder = BufferEncoder::new();
+        LazyMap::<u32, Counted>::encode_map(&map, &mut encoder).unwrap();
+        let bytes = encoder.as_bytes().to_vec();
+
+        let lazy = LazyMap::<u32, Counted>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(lazy.len(), 4);
+        assert_eq!(DECODED.load(Ordering::SeqCst), 0);
+
+        assert_eq!(lazy.get(&2).unwrap(), Some(&Counted(20)));
+        assert_eq!(lazy.get(&2).unwrap(), Some(&Counted(20)));
+        assert_eq!(lazy.get(&9).unwrap(), None);
+        assert_eq!(DECODED.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn hash_map_bytes_are_not_a_lazy_map() {
+        let map = HashMap::from([(1u32, 2u32)]);
+        let bytes = map.encode_to_bytes().unwrap();
+        assert!(LazyMap::<u32, u32>::decode_from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn trailing_bytes_in_a_value_frame_are_rejected() {
+        let mut encoder = BufferEncoder::new();
+        encoder.encode_len(1).unwrap();
+        encoder.encode_u32(1).unwrap();
+        encoder.encode_u32(6).unwrap();
+        encoder.encode_u32(7).unwrap();
+        encoder.encode_bytes(&[0, 0]).unwrap();
+        let bytes = encoder.as_bytes().to_vec();
+
+        let lazy = LazyMap::<u32, u32>::decode_from_bytes(&bytes).unwrap();
+        let err = lazy.get(&1).unwrap_err();
+        assert_eq!(err.position(), Some(4));
+        assert_eq!(err.kind().to_string(), "Lazy value has trailing bytes");
+        assert!(lazy.get(&1).is_err());
+    }
+}
@@ -1,6 +1,133 @@
+#[cfg(test)]
+extern crate self as binrs;
+
+#[cfg(feature = "base64")]
+pub mod base64;
 pub mod context;
 pub mod converter;
+#[cfg(feature = "crc32")]
+pub mod crc32;
 pub mod decoder;
 pub mod encoder;
 pub mod endian;
+pub mod envelope;
 pub mod error;
+pub mod fixed;
+pub mod hashing;
+pub mod hex;
+pub mod instrument;
+pub mod lazy;
+pub mod marker;
+pub mod net;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod time;
+pub mod varint;
+pub mod varwidth;
+
+#[doc(hidden)]
+pub mod private;
+pub mod shared;
+
+/// Everything needed to encode and decode a type with a single import.
+///
+/// ```
+/// use binrs::prelude::*;
+///
+/// #[derive(Debug, PartialEq, Encode, Decode)]
+/// struct User {
+///     id: u64,
+///     username: String,
+///     email: Option<String>,
+///     roles: Vec<String>,
+///     #[bin(skip)]
+///     ignored: String,
+/// }
+///
+/// let user = User {
+///     id: 1001,
+///     username: "johndoe".to_string(),
+///     email: Some("john@example.com".to_string()),
+///     roles: vec!["admin".to_string()],
+///     ignored: "Ignored".to_string(),
+/// };
+///
+/// let bytes = user.encode_to_bytes()?;
+/// let decoded = User::decode_from_bytes(&bytes)?;
+/// assert_eq!(decoded, User { ignored: String::new(), ..user });
+/// # Ok::<(), Error>(())
+/// ```
+pub mod prelude {
+    pub use crate::{
+        context::Context,
+        decoder::{BufferDecoder, Decode, DecodeBorrowed, DecodeSeed, Decoder},
+        encoder::{BufferEncoder, Encode, Encoder},
+        endian::Endianness,
+        error::Error,
+    };
+    /// Derives `Decode` from the same `#[bin(...)]` attributes as `Encode`.
+    ///
+    /// Defaulted fields need a type implementing `Default`:
+    ///
+    /// ```compile_fail,E0277
+    /// use binrs::prelude::*;
+    ///
+    /// struct Handle(u32);
+    ///
+    /// #[derive(Encode, Decode)]
+    /// struct Packet {
+    ///     id: u32,
+    ///     #[bin(skip, default)]
+    ///     handle: Handle,
+    /// }
+    /// ```
+    pub use binrs_derive::Decode;
+    /// Derives `Encode`, configured through `#[bin(...)]` attributes.
+    ///
+    /// `#[bin(at = N)]` pads so a field starts `N` bytes after the first field,
+    /// which comes after any `magic`. A field left out by `since` or `skip_if`
+    /// is neither padded nor skipped to; later fields still land at their own
+    /// offsets.
+    ///
+    /// Unknown attribute keys are rejected:
+    ///
+    /// ```compile_fail
+    /// use binrs::prelude::*;
+    ///
+    /// #[derive(Encode, Decode)]
+    /// struct Packet {
+    ///     #[bin(compress)]
+    ///     payload: Vec<u8>,
+    /// }
+    /// ```
+    ///
+    /// A field takes at most one codec attribute:
+    ///
+    /// ```compile_fail
+    /// use binrs::prelude::*;
+    ///
+    /// #[derive(Encode, Decode)]
+    /// struct Packet {
+    ///     #[bin(varint, with = "binrs::varint")]
+    ///     length: u64,
+    /// }
+    /// ```
+    ///
+    /// `skip_if` is only allowed on the last decoded field:
+    ///
+    /// ```compile_fail
+    /// use binrs::prelude::*;
+    ///
+    /// fn is_zero(value: &u32) -> bool {
+    ///     *value == 0
+    /// }
+    ///
+    /// #[derive(Encode, Decode)]
+    /// struct Packet {
+    ///     #[bin(skip_if = "is_zero", default)]
+    ///     flags: u32,
+    ///     id: u32,
+    /// }
+    /// ```
+    pub use binrs_derive::Encode;
+}
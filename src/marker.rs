@@ -0,0 +1,79 @@
+use std::marker::{PhantomData, PhantomPinned};
+
+use crate::{
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+impl Encode for () {
+    fn encode<E: Encoder>(&self, _encoder: &mut E) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Decode for () {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    fn decode<D: Decoder>(_decoder: &mut D) -> Result<Self, Error> {
+        Ok(())
+    }
+}
+
+impl Encode for PhantomPinned {
+    fn encode<E: Encoder>(&self, _encoder: &mut E) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Decode for PhantomPinned {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    fn decode<D: Decoder>(_decoder: &mut D) -> Result<Self, Error> {
+        Ok(PhantomPinned)
+    }
+}
+
+impl<T: ?Sized> Encode for PhantomData<T> {
+    fn encode<E: Encoder>(&self, _encoder: &mut E) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<T: ?Sized> Decode for PhantomData<T> {
+    const FIXED_SIZE: Option<usize> = Some(0);
+
+    fn decode<D: Decoder>(_decoder: &mut D) -> Result<Self, Error> {
+        Ok(PhantomData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::{PhantomData, PhantomPinned};
+
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Pinned {
+        raw: PhantomData<*const u8>,
+        pin: PhantomPinned,
+        unit: (),
+    }
+
+    #[test]
+    fn markers_produce_and_consume_no_bytes() {
+        let value = Pinned {
+            raw: PhantomData,
+            pin: PhantomPinned,
+            unit: (),
+        };
+        assert!(value.encode_to_bytes().unwrap().is_empty());
+        assert_eq!(Pinned::FIXED_SIZE, Some(0));
+
+        let bytes = [1, 2];
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(Pinned::decode(&mut decoder).unwrap(), value);
+        assert_eq!(decoder.position(), 0);
+    }
+}
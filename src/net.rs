@@ -0,0 +1,84 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::{
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+impl Encode for Ipv4Addr {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_bytes(&self.octets())
+    }
+}
+
+impl Decode for Ipv4Addr {
+    const MIN_SIZE: usize = 4;
+    const FIXED_SIZE: Option<usize> = Some(4);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Ipv4Addr::from(<[u8; 4]>::decode(decoder)?))
+    }
+}
+
+impl Encode for Ipv6Addr {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_bytes(&self.octets())
+    }
+}
+
+impl Decode for Ipv6Addr {
+    const MIN_SIZE: usize = 16;
+    const FIXED_SIZE: Option<usize> = Some(16);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        Ok(Ipv6Addr::from(<[u8; 16]>::decode(decoder)?))
+    }
+}
+
+impl Encode for IpAddr {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        match self {
+            IpAddr::V4(addr) => {
+                encoder.encode_u8(0)?;
+                addr.encode(encoder)
+            }
+            IpAddr::V6(addr) => {
+                encoder.encode_u8(1)?;
+                addr.encode(encoder)
+            }
+        }
+    }
+}
+
+impl Decode for IpAddr {
+    const MIN_SIZE: usize = 5;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let start = decoder.position();
+        match decoder.decode_u8()? {
+            0 => Ok(IpAddr::V4(Ipv4Addr::decode(decoder)?)),
+            1 => Ok(IpAddr::V6(Ipv6Addr::decode(decoder)?)),
+            _ => Err(Error::at(start, Error::InvalidTag)),
+        }
+    }
+}
+
+// Only the address and port are written; the flow info and scope id of a V6
+// socket address decode as zero.
+impl Encode for SocketAddr {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        self.ip().encode(encoder)?;
+        encoder.encode_u16(self.port())
+    }
+}
+
+impl Decode for SocketAddr {
+    const MIN_SIZE: usize = IpAddr::MIN_SIZE + 2;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let ip = IpAddr::decode(decoder)?;
+        let port = decoder.decode_u16()?;
+        Ok(SocketAddr::new(ip, port))
+    }
+}
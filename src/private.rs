@@ -0,0 +1,1026 @@
+use crate::{
+    context::Context,
+    decoder::{Decode, Decoder, preallocate},
+    encoder::{Encode, Encoder},
+    error::Error,
+    shared::EncodeTable,
+};
+
+pub fn encode_none_is_empty<T, E>(value: &Option<T>, encoder: &mut E) -> Result<(), Error>
+where
+    T: Encode + Default + PartialEq,
+    E: Encoder,
+{
+    match value {
+        Some(value) if *value == T::default() => {
+            Err("Ambiguous empty value for none_is_empty".into())
+        }
+        Some(value) => value.encode(encoder),
+        None => T::default().encode(encoder),
+    }
+}
+
+pub fn decode_none_is_empty<T, D>(decoder: &mut D) -> Result<Option<T>, Error>
+where
+    T: Decode + Default + PartialEq,
+    D: Decoder,
+{
+    let value = T::decode(decoder)?;
+    Ok((value != T::default()).then_some(value))
+}
+
+pub fn encode_with_context<E, F>(encoder: &mut E, context: Context, encode: F) -> Result<(), Error>
+where
+    E: Encoder,
+    F: FnOnce(&mut E) -> Result<(), Error>,
+{
+    let previous = encoder.context();
+    encoder.set_context(context);
+    let result = encode(encoder);
+    encoder.set_context(previous);
+    result
+}
+
+pub fn decode_with_context<T, D, F>(
+    decoder: &mut D,
+    context: Context,
+    decode: F,
+) -> Result<T, Error>
+where
+    D: Decoder,
+    F: FnOnce(&mut D) -> Result<T, Error>,
+{
+    let previous = decoder.context();
+    decoder.set_context(context);
+    let result = decode(decoder);
+    decoder.set_context(previous);
+    result
+}
+
+pub fn decode_nested<T, D, F>(decoder: &mut D, decode: F) -> Result<T, Error>
+where
+    D: Decoder,
+    F: FnOnce(&mut D) -> Result<T, Error>,
+{
+    decoder.enter()?;
+    let result = decode(decoder);
+    decoder.leave();
+    result
+}
+
+pub fn encode_framed<E, F>(encoder: &mut E, encode: F) -> Result<(), Error>
+where
+    E: Encoder,
+    F: FnOnce(&mut FrameEncoder) -> Result<(), Error>,
+{
+    // The payload is buffered once so its length can be written first. The
+    // frame borrows the caller's shared table and replays field hooks when it
+    // is copied out, and being a concrete type it keeps recursive enums from
+    // instantiating a new encoder type per nesting level.
+    let mut frame = FrameEncoder {
+        buffer: Vec::new(),
+        start: encoder.position() + size_of::<u32>(),
+        context: encoder.context(),
+        table: encoder.shared_table().map(std::mem::take),
+        fields: Vec::new(),
+    };
+    let result = encode(&mut frame);
+    if let Some(table) = frame.table.take()
+        && let Some(shared) = encoder.shared_table()
+    {
+        *shared = table;
+    }
+    result?;
+    encoder.set_context(frame.context);
+
+    let len = u32::try_from(frame.buffer.len()).map_err(|_| "Variant too large")?;
+    encoder.encode_u32(len)?;
+    let mut written = 0;
+    for (offset, field) in &frame.fields {
+        encoder.encode_bytes(&frame.buffer[written..*offset])?;
+        written = *offset;
+        match field {
+            FrameField::Begin(name) => encoder.begin_field(name),
+            FrameField::End(name) => encoder.end_field(name),
+        }
+    }
+    encoder.encode_bytes(&frame.buffer[written..])
+}
+
+enum FrameField {
+    Begin(String),
+    End(String),
+}
+
+pub struct FrameEncoder {
+    buffer: Vec<u8>,
+    start: usize,
+    context: Context,
+    table: Option<EncodeTable>,
+    fields: Vec<(usize, FrameField)>,
+}
+
+impl Encoder for FrameEncoder {
+    fn context(&self) -> Context {
+        self.context
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.context = context;
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.buffer.extend_from_slice(slice);
+        Ok(())
+    }
+
+    fn position(&self) -> usize {
+        self.start + self.buffer.len()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.fields
+            .push((self.buffer.len(), FrameField::Begin(name.to_string())));
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.fields
+            .push((self.buffer.len(), FrameField::End(name.to_string())));
+    }
+
+    fn shared_table(&mut self) -> Option<&mut EncodeTable> {
+        self.table.as_mut()
+    }
+}
+
+pub fn decode_framed<T, D, F>(decoder: &mut D, decode: F) -> Result<T, Error>
+where
+    D: Decoder,
+    F: FnOnce(&mut D) -> Result<T, Error>,
+{
+    let len = decoder.decode_u32()? as usize;
+    let start = decoder.position();
+    let end = start.saturating_add(len);
+    let context = decoder.context();
+    if let Some(outer) = context.frame_end
+        && end > outer
+    {
+        return Err(Error::at(
+            start,
+            Error::UnexpectedEof {
+                needed: len,
+                remaining: outer.saturating_sub(start),
+            },
+        ));
+    }
+
+    decoder.set_context(Context {
+        frame_end: Some(end),
+        ..context
+    });
+    let result = decode(decoder);
+    decoder.set_context(Context {
+        frame_end: context.frame_end,
+        ..decoder.context()
+    });
+    let value = result?;
+    decoder.skip_bytes(end.saturating_sub(decoder.position()))?;
+    Ok(value)
+}
+
+pub fn skip_framed<D: Decoder>(decoder: &mut D) -> Result<(), Error> {
+    let len = decoder.decode_u32()? as usize;
+    decoder.skip_bytes(len)
+}
+
+pub const fn sum_fixed_sizes(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut total = 0;
+    let mut i = 0;
+    while i < sizes.len() {
+        match sizes[i] {
+            Some(size) => total += size,
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(total)
+}
+
+pub fn encode_len_before_tag<T, E>(value: &Option<Vec<T>>, encoder: &mut E) -> Result<(), Error>
+where
+    T: Encode,
+    E: Encoder,
+{
+    match value {
+        Some(items) => {
+            encoder.encode_len(items.len())?;
+            encoder.encode_u8(1)?;
+            for item in items {
+                item.encode(encoder)?;
+            }
+            Ok(())
+        }
+        None => {
+            encoder.encode_len(0)?;
+            encoder.encode_u8(0)
+        }
+    }
+}
+
+pub fn decode_len_before_tag<T, D>(decoder: &mut D) -> Result<Option<Vec<T>>, Error>
+where
+    T: Decode,
+    D: Decoder,
+{
+    let len = decoder.decode_len()?;
+    let start = decoder.position();
+    match decoder.decode_u8()? {
+        0 if len == 0 => Ok(None),
+        1 => {
+            let mut items = Vec::with_capacity(preallocate::<T, _>(decoder, len)?);
+            for _ in 0..len {
+                items.push(T::decode(decoder)?);
+            }
+            Ok(Some(items))
+        }
+        _ => Err(Error::at(start, Error::InvalidTag)),
+    }
+}
+
+pub fn encode_padding_to<E: Encoder>(
+    encoder: &mut E,
+    start: usize,
+    offset: usize,
+) -> Result<(), Error> {
+    let position = encoder.position() - start;
+    if position > offset {
+        return Err("Field offset already passed".into());
+    }
+    encoder.pad(offset - position, 0)
+}
+
+pub fn skip_to<D: Decoder>(decoder: &mut D, start: usize, offset: usize) -> Result<(), Error> {
+    let position = decoder.position() - start;
+    if position > offset {
+        return Err("Field offset already passed".into());
+    }
+    decoder.skip_bytes(offset - position)
+}
+
+// `nullable_ptr` writes a logical pointer: a `u64` zero for `None`, or a
+// nonzero marker followed by the value. No real address is ever written, and
+// the width is fixed so the format does not depend on the host.
+pub fn encode_nullable_ptr<T, E>(value: &Option<Box<T>>, encoder: &mut E) -> Result<(), Error>
+where
+    T: Encode,
+    E: Encoder,
+{
+    match value {
+        Some(value) => {
+            encoder.encode_u64(1)?;
+            value.encode(encoder)
+        }
+        None => encoder.encode_u64(0),
+    }
+}
+
+pub fn decode_nullable_ptr<T, D>(decoder: &mut D) -> Result<Option<Box<T>>, Error>
+where
+    T: Decode,
+    D: Decoder,
+{
+    match decoder.decode_u64()? {
+        0 => Ok(None),
+        _ => Ok(Some(Box::new(T::decode(decoder)?))),
+    }
+}
+
+pub fn decode_magic<T, D>(decoder: &mut D, magic: &T) -> Result<(), Error>
+where
+    T: Decode + PartialEq,
+    D: Decoder,
+{
+    if T::decode(decoder)? != *magic {
+        return Err("Bad magic".into());
+    }
+    Ok(())
+}
+
+pub fn take_value_end<D: Decoder>(decoder: &mut D) -> Option<usize> {
+    let context = decoder.context();
+    decoder.set_context(Context {
+        value_end: None,
+        ..context
+    });
+    context.value_end
+}
+
+pub fn frame_end<D: Decoder>(decoder: &D) -> Option<usize> {
+    decoder.context().frame_end
+}
+
+/// Whether a `#[bin(default)]` field has run out of input.
+///
+/// `end` is only known for a value decoded through `decode_from_bytes` or
+/// `decode_with_ctx`, or for a variant inside a sized frame. Anywhere else the
+/// end of the input belongs to some enclosing value, so the field is rejected
+/// rather than reading its neighbour's bytes.
+pub fn at_end<D: Decoder>(decoder: &D, end: Option<usize>) -> Result<bool, Error> {
+    match end {
+        Some(end) => Ok(decoder.position() >= end),
+        None => {
+            Err(decoder
+                .error("Default fields can only be omitted from a top-level or framed value"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, rc::Rc};
+
+    use crate::{
+        decoder::ReadDecoder,
+        instrument::InstrumentedCodec,
+        prelude::*,
+        shared::{Shared, SharedDecoder, SharedEncoder},
+    };
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Legacy {
+        #[bin(none_is_empty)]
+        name: Option<String>,
+    }
+
+    #[test]
+    fn none_is_empty_round_trips() {
+        for name in [Some("x".to_string()), None] {
+            let legacy = Legacy { name };
+            let bytes = legacy.encode_to_bytes().unwrap();
+            assert_eq!(Legacy::decode_from_bytes(&bytes).unwrap(), legacy);
+        }
+
+        let bytes = Legacy { name: None }.encode_to_bytes().unwrap();
+        assert_eq!(bytes, String::new().encode_to_bytes().unwrap());
+    }
+
+    #[test]
+    fn none_is_empty_rejects_ambiguous_empty_value() {
+        let err = Legacy {
+            name: Some(String::new()),
+        }
+        .encode_to_bytes()
+        .unwrap_err();
+        assert!(
+            matches!(err.kind(), Error::Custom(message) if message == "Ambiguous empty value for none_is_empty")
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(tag = "string")]
+    enum Command {
+        Ping,
+        Echo(String),
+        Move { x: i16, y: i16 },
+    }
+
+    #[test]
+    fn string_tags_round_trip() {
+        for command in [
+            Command::Ping,
+            Command::Echo("hi".to_string()),
+            Command::Move { x: -1, y: 2 },
+        ] {
+            let bytes = command.encode_to_bytes().unwrap();
+            assert_eq!(Command::decode_from_bytes(&bytes).unwrap(), command);
+        }
+
+        let bytes = Command::Ping.encode_to_bytes().unwrap();
+        assert_eq!(bytes, "Ping".encode_to_bytes().unwrap());
+    }
+
+    #[test]
+    fn unknown_string_tag_errors() {
+        let bytes = "Jump".encode_to_bytes().unwrap();
+        let err = Command::decode_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), Error::InvalidTag));
+        assert_eq!(err.position(), Some(0));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum EventV2 {
+        Start(u32),
+        Stop,
+        Resize { width: u32, height: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(other = "Unknown")]
+    enum EventV1 {
+        Start(u32),
+        Stop,
+        Unknown,
+    }
+
+    #[test]
+    fn unknown_tag_decodes_into_other_variant() {
+        let newer = (
+            EventV2::Resize {
+                width: 640,
+                height: 480,
+            },
+            EventV2::Start(9),
+        );
+        let bytes = newer.encode_to_bytes().unwrap();
+        let older = <(EventV1, EventV1)>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(older, (EventV1::Unknown, EventV1::Start(9)));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum ExprV2 {
+        Lit(i32),
+        Neg(Box<ExprV2>),
+        Add(Box<ExprV2>, Box<ExprV2>),
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(other = "Unknown")]
+    enum ExprV1 {
+        Lit(i32),
+        Neg(Box<ExprV1>),
+        Unknown,
+    }
+
+    #[test]
+    fn recursive_enum_falls_back_to_other_variant() {
+        let value = ExprV1::Neg(Box::new(ExprV1::Neg(Box::new(ExprV1::Lit(3)))));
+        let bytes = value.encode_to_bytes().unwrap();
+        assert_eq!(ExprV1::decode_from_bytes(&bytes).unwrap(), value);
+
+        let newer = ExprV2::Neg(Box::new(ExprV2::Add(
+            Box::new(ExprV2::Lit(1)),
+            Box::new(ExprV2::Lit(2)),
+        )));
+        let bytes = (newer, ExprV2::Lit(4)).encode_to_bytes().unwrap();
+        assert_eq!(
+            <(ExprV1, ExprV1)>::decode_from_bytes(&bytes).unwrap(),
+            (ExprV1::Neg(Box::new(ExprV1::Unknown)), ExprV1::Lit(4))
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum SizedEventV1 {
+        Start(u32),
+        Stop,
+    }
+
+    #[test]
+    fn unknown_sized_variant_is_skipped() {
+        let bytes = (
+            EventV2::Resize {
+                width: 640,
+                height: 480,
+            },
+            0xdead_beef_u32,
+        )
+            .encode_to_bytes()
+            .unwrap();
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        let err = SizedEventV1::decode(&mut decoder).unwrap_err();
+        assert!(matches!(err.kind(), Error::InvalidEnumVariant(2)));
+        assert_eq!(u32::decode(&mut decoder).unwrap(), 0xdead_beef);
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum Tree {
+        Leaf(u32),
+        Node(Box<Tree>, Box<Tree>),
+    }
+
+    fn tree(depth: u32) -> Tree {
+        match depth {
+            0 => Tree::Leaf(7),
+            _ => Tree::Node(Box::new(tree(depth - 1)), Box::new(Tree::Leaf(depth))),
+        }
+    }
+
+    #[test]
+    fn recursive_sized_variants_round_trip() {
+        let value = tree(6);
+        let bytes = value.encode_to_bytes().unwrap();
+        // Every leaf is a tag, a frame length and a u32; every node a tag and
+        // a frame length.
+        assert_eq!(bytes.len(), 7 * 12 + 6 * 8);
+        assert_eq!(Tree::decode_from_bytes(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn nested_frames_cannot_outgrow_their_parent() {
+        let mut bytes = tree(1).encode_to_bytes().unwrap();
+        // Claim a longer left child than the node's own frame holds.
+        bytes[12] = 200;
+        let err = Tree::decode_from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), Error::UnexpectedEof { .. }));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum SharedPair {
+        Pair {
+            left: Shared<Rc<String>>,
+            right: Shared<Rc<String>>,
+        },
+    }
+
+    #[test]
+    fn framed_variants_keep_shared_references() {
+        let name = Rc::new("shared".to_string());
+        let value = SharedPair::Pair {
+            left: Shared(Rc::clone(&name)),
+            right: Shared(name),
+        };
+
+        let mut fields = Vec::new();
+        let mut encoder = InstrumentedCodec::new(SharedEncoder::new(BufferEncoder::new()))
+            .on_encode_field(|name, len| fields.push((name.to_string(), len)));
+        value.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_inner().into_inner().into_bytes();
+        assert_eq!(fields.len(), 2);
+        assert!(fields.iter().all(|(_, len)| *len > 0));
+
+        let mut decoder = SharedDecoder::new(BufferDecoder::new(&bytes));
+        let SharedPair::Pair { left, right } = SharedPair::decode(&mut decoder).unwrap();
+        assert!(Rc::ptr_eq(&left.0, &right.0));
+        assert_eq!(decoder.into_inner().remaining(), 0);
+    }
+
+    #[test]
+    fn canonical_entries_keep_shared_references() {
+        let value = Rc::new("shared".to_string());
+        let map: HashMap<u8, Shared<Rc<String>>> =
+            (0..8).map(|key| (key, Shared(Rc::clone(&value)))).collect();
+        let ctx = Context::new(Endianness::Little).with_canonical(true);
+
+        let mut encoder = SharedEncoder::new(BufferEncoder::with_ctx(ctx));
+        map.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_inner().into_bytes();
+
+        let mut decoder = SharedDecoder::new(BufferDecoder::new(&bytes));
+        let decoded = HashMap::<u8, Shared<Rc<String>>>::decode(&mut decoder).unwrap();
+        assert_eq!(decoded, map);
+        assert!(decoded.values().all(|v| Rc::ptr_eq(&v.0, &decoded[&0].0)));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Node {
+        value: u16,
+        #[bin(nullable_ptr)]
+        next: Option<Box<Node>>,
+    }
+
+    #[test]
+    fn nullable_ptr_round_trips_with_u64_markers() {
+        let tail = Node {
+            value: 2,
+            next: None,
+        };
+        let bytes = tail.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Node::decode_from_bytes(&bytes).unwrap(), tail);
+
+        let head = Node {
+            value: 1,
+            next: Some(Box::new(tail)),
+        };
+        let bytes = head.encode_to_bytes().unwrap();
+        assert_eq!(&bytes[..10], [1, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Node::decode_from_bytes(&bytes).unwrap(), head);
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Meta(u8);
+
+    mod reversed {
+        use crate::prelude::*;
+
+        pub fn encode<E: Encoder>(value: &u16, encoder: &mut E) -> Result<(), Error> {
+            value.swap_bytes().encode(encoder)
+        }
+
+        pub fn decode<D: Decoder>(decoder: &mut D) -> Result<u16, Error> {
+            Ok(u16::decode(decoder)?.swap_bytes())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Packet(
+        #[bin(skip)] Meta,
+        #[bin(with = "reversed")] u16,
+        #[bin(default = "7")] u32,
+    );
+
+    #[test]
+    fn tuple_struct_fields_take_attributes() {
+        let packet = Packet(Meta(9), 0x0102, 3);
+        let bytes = packet.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [0x01, 0x02, 3, 0, 0, 0]);
+        assert_eq!(
+            Packet::decode_from_bytes(&bytes).unwrap(),
+            Packet(Meta(0), 0x0102, 3)
+        );
+    }
+
+    #[test]
+    fn missing_trailing_default_field_uses_its_default() {
+        assert_eq!(
+            Packet::decode_from_bytes(&[0x01, 0x02]).unwrap(),
+            Packet(Meta(0), 0x0102, 7)
+        );
+    }
+
+    #[test]
+    fn default_fields_reject_decoders_without_a_length() {
+        let bytes = Packet(Meta(0), 1, 2).encode_to_bytes().unwrap();
+        let mut decoder = ReadDecoder::new(bytes.as_slice());
+        let err = Packet::decode(&mut decoder).unwrap_err();
+        assert!(err.to_string().contains("top-level or framed"));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Wrapper {
+        packet: Packet,
+        next: u32,
+    }
+
+    #[test]
+    fn nested_default_field_does_not_consume_the_parent() {
+        let bytes = Wrapper {
+            packet: Packet(Meta(0), 1, 2),
+            next: 3,
+        }
+        .encode_to_bytes()
+        .unwrap();
+        let err = Wrapper::decode_from_bytes(&bytes).unwrap_err();
+        assert!(err.to_string().contains("top-level or framed"));
+        assert_eq!(err.position(), Some(2));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum Versioned {
+        Packet(u16, #[bin(default = "7")] u32),
+    }
+
+    #[test]
+    fn framed_default_field_stops_at_the_frame_end() {
+        // Tag 0, then a frame holding only the u16 an older writer knew about.
+        let bytes = (0u32, 2u32, 1u16, 9u32).encode_to_bytes().unwrap();
+        let decoded = <(Versioned, u32)>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, (Versioned::Packet(1, 7), 9));
+    }
+
+    fn is_zero(value: &u16) -> bool {
+        *value == 0
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Reading {
+        value: u32,
+        #[bin(skip_if = "is_zero", default)]
+        flags: u16,
+        #[bin(skip)]
+        cached: Option<u32>,
+    }
+
+    #[test]
+    fn skipped_trailing_field_decodes_as_default() {
+        let reading = Reading {
+            value: 5,
+            flags: 0,
+            cached: None,
+        };
+        let bytes = reading.encode_to_bytes().unwrap();
+        assert_eq!(bytes, 5u32.encode_to_bytes().unwrap());
+        assert_eq!(Reading::decode_from_bytes(&bytes).unwrap(), reading);
+    }
+
+    #[test]
+    fn written_trailing_field_round_trips() {
+        let reading = Reading {
+            value: 5,
+            flags: 3,
+            cached: None,
+        };
+        let bytes = reading.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [5, 0, 0, 0, 3, 0]);
+        assert_eq!(Reading::decode_from_bytes(&bytes).unwrap(), reading);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Outer {
+        reading: Reading,
+        tail: u32,
+    }
+
+    #[test]
+    fn nested_skip_if_field_is_rejected() {
+        let outer = Outer {
+            reading: Reading {
+                value: 1,
+                flags: 0,
+                cached: None,
+            },
+            tail: 0xaabb_ccdd,
+        };
+        let bytes = outer.encode_to_bytes().unwrap();
+        let err = Outer::decode_from_bytes(&bytes).unwrap_err();
+        assert!(err.to_string().contains("top-level or framed"));
+        assert_eq!(err.position(), Some(4));
+
+        let readings = vec![
+            outer.reading,
+            Reading {
+                value: 2,
+                flags: 3,
+                cached: None,
+            },
+        ];
+        let bytes = readings.encode_to_bytes().unwrap();
+        let err = Vec::<Reading>::decode_from_bytes(&bytes).unwrap_err();
+        assert!(err.to_string().contains("top-level or framed"));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(sized_variants)]
+    enum Sample {
+        Reading {
+            value: u32,
+            #[bin(skip_if = "is_zero", default)]
+            flags: u16,
+        },
+    }
+
+    #[test]
+    fn framed_skip_if_field_round_trips_when_nested() {
+        let samples = vec![
+            Sample::Reading { value: 1, flags: 0 },
+            Sample::Reading { value: 2, flags: 3 },
+        ];
+        let bytes = samples.encode_to_bytes().unwrap();
+        assert_eq!(Vec::<Sample>::decode_from_bytes(&bytes).unwrap(), samples);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(magic = b"LY")]
+    struct Layout {
+        #[bin(at = 0)]
+        kind: u16,
+        #[bin(at = 4)]
+        len: u32,
+        #[bin(at = 16)]
+        flags: u8,
+    }
+
+    #[test]
+    fn field_offsets_are_measured_after_the_magic() {
+        let layout = Layout {
+            kind: 1,
+            len: 2,
+            flags: 3,
+        };
+        let bytes = layout.encode_to_bytes().unwrap();
+        assert_eq!(bytes.len(), 2 + 17);
+        assert_eq!(&bytes[..2], b"LY");
+        assert_eq!(&bytes[2..4], &[1, 0]);
+        assert_eq!(&bytes[6..10], &[2, 0, 0, 0]);
+        assert_eq!(bytes[18], 3);
+        assert!(bytes[4..6].iter().chain(&bytes[10..18]).all(|b| *b == 0));
+        assert_eq!(Layout::decode_from_bytes(&bytes).unwrap(), layout);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct VersionedLayout {
+        #[bin(at = 0)]
+        kind: u16,
+        #[bin(at = 4)]
+        flags: u8,
+        #[bin(since = 2, at = 12)]
+        len: u32,
+    }
+
+    #[test]
+    fn fields_missing_from_older_versions_are_not_padded_to() {
+        for (version, len, size) in [(1, 0, 5), (2, 7, 16)] {
+            let ctx = Context::new(Endianness::Little).with_stream_version(version);
+            let layout = VersionedLayout {
+                kind: 1,
+                flags: 3,
+                len,
+            };
+            let bytes = layout.encode_with_ctx(ctx).unwrap();
+            assert_eq!(bytes.len(), size);
+            assert_eq!(
+                VersionedLayout::decode_with_ctx(&bytes, ctx).unwrap(),
+                layout
+            );
+        }
+    }
+
+    fn uppercase(name: &str) -> String {
+        name.to_uppercase()
+    }
+
+    fn lowercase(name: String) -> String {
+        name.to_lowercase()
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Label {
+        #[bin(pre = "uppercase", post = "lowercase")]
+        name: String,
+    }
+
+    #[test]
+    fn pre_and_post_hooks_transform_the_field() {
+        let label = Label {
+            name: "Hello".to_string(),
+        };
+        let bytes = label.encode_to_bytes().unwrap();
+        assert_eq!(bytes, "HELLO".encode_to_bytes().unwrap());
+        assert_eq!(
+            Label::decode_from_bytes(&bytes).unwrap(),
+            Label {
+                name: "hello".to_string()
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct TagFirst {
+        #[bin(tag_before_len)]
+        items: Option<Vec<u16>>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct LenFirst {
+        #[bin(tag_before_len = false)]
+        items: Option<Vec<u16>>,
+    }
+
+    #[test]
+    fn option_tag_and_length_round_trip_in_either_order() {
+        let list = vec![7u16, 8].encode_to_bytes().unwrap();
+        let (len, items) = list.split_at(list.len() - 4);
+
+        let tag_first = TagFirst {
+            items: Some(vec![7, 8]),
+        };
+        let bytes = tag_first.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [&[1], len, items].concat());
+        assert_eq!(TagFirst::decode_from_bytes(&bytes).unwrap(), tag_first);
+
+        let len_first = LenFirst {
+            items: Some(vec![7, 8]),
+        };
+        let bytes = len_first.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [len, &[1], items].concat());
+        assert_eq!(LenFirst::decode_from_bytes(&bytes).unwrap(), len_first);
+
+        let bytes = TagFirst { items: None }.encode_to_bytes().unwrap();
+        assert_eq!(bytes, [0]);
+        assert_eq!(
+            TagFirst::decode_from_bytes(&bytes).unwrap(),
+            TagFirst { items: None }
+        );
+
+        let bytes = LenFirst { items: None }.encode_to_bytes().unwrap();
+        assert_eq!(
+            bytes,
+            [&Vec::<u16>::new().encode_to_bytes().unwrap()[..], &[0]].concat()
+        );
+        assert_eq!(
+            LenFirst::decode_from_bytes(&bytes).unwrap(),
+            LenFirst { items: None }
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct ConfigV1 {
+        id: u32,
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct ConfigV3 {
+        id: u32,
+        name: String,
+        #[bin(since = 2)]
+        retries: u8,
+        #[bin(since = 3)]
+        label: String,
+    }
+
+    #[test]
+    fn newer_reader_defaults_fields_missing_from_an_older_stream() {
+        let mut encoder = BufferEncoder::new();
+        encoder.write_version_header(1).unwrap();
+        ConfigV1 {
+            id: 9,
+            name: "old".to_string(),
+        }
+        .encode(&mut encoder)
+        .unwrap();
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(decoder.read_version_header().unwrap(), 1);
+        assert_eq!(
+            ConfigV3::decode(&mut decoder).unwrap(),
+            ConfigV3 {
+                id: 9,
+                name: "old".to_string(),
+                retries: 0,
+                label: String::new(),
+            }
+        );
+        assert_eq!(decoder.remaining(), 0);
+
+        let current = ConfigV3 {
+            id: 9,
+            name: "new".to_string(),
+            retries: 3,
+            label: "primary".to_string(),
+        };
+        let mut encoder = BufferEncoder::new();
+        encoder.write_version_header(3).unwrap();
+        current.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(decoder.read_version_header().unwrap(), 3);
+        assert_eq!(ConfigV3::decode(&mut decoder).unwrap(), current);
+    }
+
+    fn checksum_of(checked: &Checked) -> u8 {
+        checked
+            .payload
+            .iter()
+            .fold(0, |sum, byte| sum.wrapping_add(*byte))
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Checked {
+        payload: Vec<u8>,
+        #[bin(skip, compute = "checksum_of")]
+        checksum: u8,
+    }
+
+    fn stamped_checksum(stamped: &Stamped) -> u32 {
+        stamped.body.len() as u32 * 31
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Stamped {
+        body: String,
+        #[bin(skip_decode, compute = "stamped_checksum")]
+        checksum: u32,
+    }
+
+    #[test]
+    fn computed_checksum_is_recomputed_after_decode() {
+        let checked = Checked {
+            payload: vec![200, 100, 7],
+            checksum: 0,
+        };
+        let bytes = checked.encode_to_bytes().unwrap();
+        assert_eq!(bytes, vec![200u8, 100, 7].encode_to_bytes().unwrap());
+        assert_eq!(
+            Checked::decode_from_bytes(&bytes).unwrap(),
+            Checked {
+                payload: vec![200, 100, 7],
+                checksum: 51,
+            }
+        );
+
+        let stale = Stamped {
+            body: "abcd".to_string(),
+            checksum: 1,
+        };
+        let bytes = stale.encode_to_bytes().unwrap();
+        assert_eq!(bytes, ("abcd", 1u32).encode_to_bytes().unwrap());
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(
+            Stamped::decode(&mut decoder).unwrap(),
+            Stamped {
+                body: "abcd".to_string(),
+                checksum: 124,
+            }
+        );
+    }
+}
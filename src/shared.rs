@@ -0,0 +1,355 @@
+use std::{any::Any, collections::HashMap, rc::Rc, sync::Arc};
+
+use crate::{
+    context::Context,
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+#[derive(Clone, Default)]
+pub struct EncodeTable {
+    ids: HashMap<usize, u32>,
+}
+
+impl EncodeTable {
+    pub fn lookup_or_insert(&mut self, ptr: usize) -> Result<Option<u32>, Error> {
+        if let Some(id) = self.ids.get(&ptr) {
+            return Ok(Some(*id));
+        }
+        let id = u32::try_from(self.ids.len()).map_err(|_| "Too many shared references")?;
+        self.ids.insert(ptr, id);
+        Ok(None)
+    }
+}
+
+#[derive(Default)]
+pub struct DecodeTable {
+    values: Vec<Option<Box<dyn Any>>>,
+}
+
+impl DecodeTable {
+    pub fn reserve(&mut self) -> usize {
+        self.values.push(None);
+        self.values.len() - 1
+    }
+
+    pub fn fill<T: Any>(&mut self, slot: usize, value: T) {
+        self.values[slot] = Some(Box::new(value));
+    }
+
+    pub fn get<T: Any + Clone>(&self, id: u32) -> Result<T, Error> {
+        match self.values.get(id as usize) {
+            Some(Some(value)) => Ok(value
+                .downcast_ref::<T>()
+                .ok_or("Shared reference type mismatch")?
+                .clone()),
+            Some(None) => Err("Cyclic shared reference".into()),
+            None => Err("Invalid shared reference".into()),
+        }
+    }
+}
+
+/// An `Rc` or `Arc` whose sharing survives a [`SharedEncoder`] and
+/// [`SharedDecoder`].
+///
+/// Under those codecs each pointer is written once, and later copies become a
+/// back-reference that decodes to the same allocation. Under any other codec
+/// the pointer is written as its value, exactly like a bare `Rc` or `Arc`.
+/// Rebuilding the sharing keeps the decoded pointer in a type-erased table, so
+/// the pointee must be `'static`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Shared<P>(pub P);
+
+impl<P> Shared<P> {
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+const BACK_REFERENCE_SIZE: usize = 5;
+
+fn encode_shared<T, E>(value: &T, ptr: usize, encoder: &mut E) -> Result<(), Error>
+where
+    T: Encode + ?Sized,
+    E: Encoder,
+{
+    if let Some(table) = encoder.shared_table() {
+        match table.lookup_or_insert(ptr)? {
+            Some(id) => {
+                encoder.encode_u8(1)?;
+                return encoder.encode_u32(id);
+            }
+            None => encoder.encode_u8(0)?,
+        }
+    }
+    value.encode(encoder)
+}
+
+fn decode_shared<P, D>(decoder: &mut D) -> Result<P, Error>
+where
+    P: Decode + Clone + 'static,
+    D: Decoder,
+{
+    if decoder.shared_table().is_none() {
+        return P::decode(decoder);
+    }
+
+    let start = decoder.position();
+    match decoder.decode_u8()? {
+        0 => {
+            let slot = decoder.shared_table().map(|table| table.reserve());
+            let value = P::decode(decoder)?;
+            if let (Some(slot), Some(table)) = (slot, decoder.shared_table()) {
+                table.fill(slot, value.clone());
+            }
+            Ok(value)
+        }
+        1 => {
+            let id = decoder.decode_u32()?;
+            match decoder.shared_table() {
+                Some(table) => table.get(id),
+                None => Err("Invalid shared reference".into()),
+            }
+        }
+        _ => Err(Error::at(start, Error::InvalidTag)),
+    }
+}
+
+const fn shared_min_size(min_size: usize) -> usize {
+    if min_size < BACK_REFERENCE_SIZE {
+        min_size
+    } else {
+        BACK_REFERENCE_SIZE
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Shared<Rc<T>> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encode_shared(&*self.0, Rc::as_ptr(&self.0).cast::<()>() as usize, encoder)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Shared<Arc<T>> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encode_shared(
+            &*self.0,
+            Arc::as_ptr(&self.0).cast::<()>() as usize,
+            encoder,
+        )
+    }
+}
+
+impl<T: ?Sized + 'static> Decode for Shared<Rc<T>>
+where
+    Rc<T>: Decode,
+{
+    const MIN_SIZE: usize = shared_min_size(Rc::<T>::MIN_SIZE);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        decode_shared(decoder).map(Shared)
+    }
+}
+
+impl<T: ?Sized + 'static> Decode for Shared<Arc<T>>
+where
+    Arc<T>: Decode,
+{
+    const MIN_SIZE: usize = shared_min_size(Arc::<T>::MIN_SIZE);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        decode_shared(decoder).map(Shared)
+    }
+}
+
+pub struct SharedEncoder<E> {
+    inner: E,
+    table: EncodeTable,
+}
+
+impl<E> SharedEncoder<E> {
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            table: EncodeTable::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Encoder> Encoder for SharedEncoder<E> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn encode_bytes(&mut self, slice: &[u8]) -> Result<(), Error> {
+        self.inner.encode_bytes(slice)
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut EncodeTable> {
+        Some(&mut self.table)
+    }
+}
+
+pub struct SharedDecoder<D> {
+    inner: D,
+    table: DecodeTable,
+}
+
+impl<D> SharedDecoder<D> {
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            table: DecodeTable::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Decoder> Decoder for SharedDecoder<D> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        self.inner.decode_bytes(len)
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        self.inner.peek_bytes(len)
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        self.inner.decode_remaining()
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        self.inner.total_len()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut DecodeTable> {
+        Some(&mut self.table)
+    }
+
+    fn track_alloc(&mut self, bytes: usize) -> Result<(), Error> {
+        self.inner.track_alloc(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{marker::PhantomData, rc::Rc, sync::Arc};
+
+    use super::{Shared, SharedDecoder, SharedEncoder};
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Node {
+        name: String,
+        weight: u32,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Dag {
+        left: Shared<Arc<Node>>,
+        right: Shared<Arc<Node>>,
+    }
+
+    #[test]
+    fn shared_arc_decodes_pointer_equal() {
+        let node = Arc::new(Node {
+            name: "leaf".to_string(),
+            weight: 3,
+        });
+        let dag = Dag {
+            left: Shared(Arc::clone(&node)),
+            right: Shared(node),
+        };
+
+        let mut encoder = SharedEncoder::new(BufferEncoder::new());
+        dag.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_inner().into_bytes();
+        assert!(bytes.len() < dag.encode_to_bytes().unwrap().len());
+
+        let mut decoder = SharedDecoder::new(BufferDecoder::new(&bytes));
+        let decoded = Dag::decode(&mut decoder).unwrap();
+        assert_eq!(decoded, dag);
+        assert!(Arc::ptr_eq(&decoded.left.0, &decoded.right.0));
+    }
+
+    #[test]
+    fn shared_pointers_are_plain_values_without_a_table() {
+        let name: Rc<str> = Rc::from("plain");
+        let pair = (Shared(Rc::clone(&name)), Shared(name));
+        let bytes = pair.encode_to_bytes().unwrap();
+        assert_eq!(bytes, ("plain", "plain").encode_to_bytes().unwrap());
+
+        let (a, b) = <(Shared<Rc<str>>, Shared<Rc<str>>)>::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(&*a.0, "plain");
+        assert!(!Rc::ptr_eq(&a.0, &b.0));
+    }
+
+    #[test]
+    fn bare_pointers_ignore_the_shared_table() {
+        let name = Rc::new("bare".to_string());
+        let pair = (Rc::clone(&name), name);
+
+        let mut encoder = SharedEncoder::new(BufferEncoder::new());
+        pair.encode(&mut encoder).unwrap();
+        let bytes = encoder.into_inner().into_bytes();
+        assert_eq!(bytes, pair.encode_to_bytes().unwrap());
+
+        let mut decoder = SharedDecoder::new(BufferDecoder::new(&bytes));
+        let (a, b) = <(Rc<String>, Rc<String>)>::decode(&mut decoder).unwrap();
+        assert_eq!(a, b);
+        assert!(!Rc::ptr_eq(&a, &b));
+    }
+
+    fn decode_borrowing(bytes: &[u8]) -> Rc<(u32, PhantomData<&[u8]>)> {
+        Rc::decode_from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn bare_pointers_decode_non_static_values() {
+        let bytes = 9u32.encode_to_bytes().unwrap();
+        assert_eq!(decode_borrowing(&bytes).0, 9);
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt::Debug;
+
+use crate::{
+    context::Context,
+    decoder::{BufferDecoder, Decode, Decoder},
+    encoder::Encode,
+    endian::Endianness,
+    error::Error,
+    shared::DecodeTable,
+};
+
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: Encode + Decode + PartialEq + Debug,
+{
+    let bytes = value.encode_to_bytes().expect("encode failed");
+    let decoded = T::decode_from_bytes(&bytes).expect("decode failed");
+    assert_eq!(*value, decoded);
+
+    assert_roundtrip_with_ctx(value, Context::new(Endianness::Big));
+}
+
+pub fn assert_roundtrip_with_ctx<T>(value: &T, ctx: Context)
+where
+    T: Encode + Decode + PartialEq + Debug,
+{
+    let bytes = value.encode_with_ctx(ctx).expect("encode failed");
+    let decoded = T::decode_with_ctx(&bytes, ctx).expect("decode failed");
+    assert_eq!(*value, decoded);
+}
+
+pub struct AllocBudget<D> {
+    inner: D,
+    budget: usize,
+    allocated: usize,
+}
+
+impl<D> AllocBudget<D> {
+    pub fn new(inner: D, budget: usize) -> Self {
+        Self {
+            inner,
+            budget,
+            allocated: 0,
+        }
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Decoder> Decoder for AllocBudget<D> {
+    fn context(&self) -> Context {
+        self.inner.context()
+    }
+
+    fn set_context(&mut self, context: Context) {
+        self.inner.set_context(context);
+    }
+
+    fn decode_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        self.inner.decode_bytes(len)
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn peek_bytes(&self, len: usize) -> Result<&[u8], Error> {
+        self.inner.peek_bytes(len)
+    }
+
+    fn decode_remaining(&mut self) -> Result<&[u8], Error> {
+        self.inner.decode_remaining()
+    }
+
+    fn total_len(&self) -> Option<usize> {
+        self.inner.total_len()
+    }
+
+    fn begin_field(&mut self, name: &str) {
+        self.inner.begin_field(name);
+    }
+
+    fn end_field(&mut self, name: &str) {
+        self.inner.end_field(name);
+    }
+
+    fn shared_table(&mut self) -> Option<&mut DecodeTable> {
+        self.inner.shared_table()
+    }
+
+    fn track_alloc(&mut self, bytes: usize) -> Result<(), Error> {
+        let allocated = self.allocated.saturating_add(bytes);
+        if allocated > self.budget {
+            return Err(Error::AllocationBudgetExceeded);
+        }
+        self.allocated = allocated;
+        self.inner.track_alloc(bytes)
+    }
+}
+
+pub fn assert_alloc_within<T: Decode>(bytes: &[u8], ctx: Context, budget: usize) {
+    let mut decoder = AllocBudget::new(BufferDecoder::with_ctx(bytes, ctx), budget);
+    if let Err(err) = T::decode(&mut decoder) {
+        assert!(
+            !matches!(err.kind(), Error::AllocationBudgetExceeded),
+            "decode requested more than {budget} bytes"
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! roundtrip_tests {
+    ($(($name:ident, $value:expr)),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                $crate::testing::assert_roundtrip(&$value);
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+        num::{NonZeroU32, Wrapping},
+        rc::Rc,
+        time::Duration,
+    };
+
+    use super::{AllocBudget, assert_alloc_within};
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Point {
+        x: i32,
+        y: i32,
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Pair(u16, String);
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Marker;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bin(tag = "string")]
+    enum Named {
+        First,
+        Second(Vec<u8>),
+    }
+
+    crate::roundtrip_tests!(
+        (roundtrip_u8, 0xabu8),
+        (roundtrip_u16, 0xabcdu16),
+        (roundtrip_u32, 0xdead_beefu32),
+        (roundtrip_u64, u64::MAX - 1),
+        (roundtrip_u128, u128::MAX / 3),
+        (roundtrip_i8, -5i8),
+        (roundtrip_i16, i16::MIN),
+        (roundtrip_i32, -123_456i32),
+        (roundtrip_i64, i64::MIN + 1),
+        (roundtrip_i128, i128::MIN / 7),
+        (roundtrip_usize, 42usize),
+        (roundtrip_f32, 1.5f32),
+        (roundtrip_f64, -2.25f64),
+        (roundtrip_bool, true),
+        (roundtrip_char, 'ß'),
+        (roundtrip_unit, ()),
+        (roundtrip_string, "hello".to_string()),
+        (roundtrip_empty_string, String::new()),
+        (roundtrip_boxed, Box::new(7u32)),
+        (roundtrip_rc_str, Rc::<str>::from("shared")),
+        (roundtrip_non_zero, NonZeroU32::new(9).unwrap()),
+        (roundtrip_wrapping, Wrapping(250u8)),
+        (roundtrip_duration, Duration::new(5, 250)),
+        (roundtrip_range, 3u32..9),
+        (roundtrip_ipv4, Ipv4Addr::new(192, 168, 0, 1)),
+        (roundtrip_ip, IpAddr::V6(Ipv6Addr::LOCALHOST)),
+        (roundtrip_socket, SocketAddr::from(([10, 0, 0, 1], 8080))),
+        (roundtrip_option_some, Some(3u8)),
+        (roundtrip_option_none, None::<u8>),
+        (roundtrip_result_ok, Ok::<u16, String>(1)),
+        (roundtrip_result_err, Err::<u16, String>("no".to_string())),
+        (roundtrip_array, [1u16, 2, 3, 4]),
+        (roundtrip_tuple, (1u8, "two".to_string(), 3.0f32)),
+        (roundtrip_vec, vec![1u32, 2, 3]),
+        (
+            roundtrip_vec_of_strings,
+            vec!["a".to_string(), "bc".to_string()]
+        ),
+        (roundtrip_vec_deque, VecDeque::from([4u8, 5, 6])),
+        (roundtrip_linked_list, LinkedList::from([7i64, -8])),
+        (
+            roundtrip_hash_map,
+            HashMap::from([("a".to_string(), 1u32), ("b".to_string(), 2)])
+        ),
+        (
+            roundtrip_btree_map,
+            BTreeMap::from([(1u8, vec![true]), (2, vec![])])
+        ),
+        (roundtrip_hash_set, HashSet::from([10u64, 20, 30])),
+        (roundtrip_btree_set, BTreeSet::from(['x', 'y'])),
+        (
+            roundtrip_struct,
+            Point {
+                x: -1,
+                y: 2,
+                label: Some("p".to_string())
+            }
+        ),
+        (roundtrip_tuple_struct, Pair(7, "seven".to_string())),
+        (roundtrip_unit_struct, Marker),
+        (roundtrip_unit_variant, Shape::Empty),
+        (roundtrip_tuple_variant, Shape::Circle(4)),
+        (
+            roundtrip_struct_variant,
+            Shape::Rect {
+                width: 3,
+                height: 5
+            }
+        ),
+        (roundtrip_string_tagged_enum, Named::Second(vec![1, 2])),
+        (roundtrip_string_tagged_unit, Named::First),
+    );
+
+    fn hostile_len() -> Vec<u8> {
+        let mut bytes = u32::MAX.encode_to_bytes().unwrap();
+        bytes.extend_from_slice(&[1, 2, 3, 4]);
+        bytes
+    }
+
+    #[test]
+    fn hostile_collection_lengths_stay_within_budget() {
+        let ctx = Context::new(Endianness::Little).with_max_preallocate(32);
+        assert_alloc_within::<Vec<u64>>(&hostile_len(), ctx, 1024);
+        assert_alloc_within::<Vec<Vec<u8>>>(&hostile_len(), ctx, 1024);
+        assert_alloc_within::<HashMap<u32, u32>>(&hostile_len(), ctx, 1024);
+        assert_alloc_within::<VecDeque<u16>>(&hostile_len(), ctx, 1024);
+    }
+
+    #[test]
+    fn large_valid_map_reserves_at_most_the_cap() {
+        let map: HashMap<u32, u64> = (0..4096).map(|i| (i, u64::from(i) * 3)).collect();
+        let bytes = map.encode_to_bytes().unwrap();
+        let ctx = Context::new(Endianness::Little).with_max_preallocate(32);
+
+        let mut decoder = AllocBudget::new(BufferDecoder::with_ctx(&bytes, ctx), usize::MAX);
+        assert_eq!(HashMap::<u32, u64>::decode(&mut decoder).unwrap(), map);
+        assert!(decoder.allocated() <= 32 * size_of::<(u32, u64)>());
+        assert!(decoder.allocated() > 0);
+    }
+
+    #[test]
+    fn hostile_string_length_fails_before_allocating() {
+        let bytes = hostile_len();
+        let mut decoder = AllocBudget::new(BufferDecoder::new(&bytes), 1024);
+        let err = String::decode(&mut decoder).unwrap_err();
+        assert!(matches!(err.kind(), Error::UnexpectedEof { .. }));
+        assert_eq!(decoder.allocated(), 0);
+    }
+
+    #[test]
+    fn budget_overrun_reports_its_variant() {
+        let bytes = vec![0u8; 4096].encode_to_bytes().unwrap();
+        let mut decoder = AllocBudget::new(BufferDecoder::new(&bytes), 1024);
+        let err = Vec::<u8>::decode(&mut decoder).unwrap_err();
+        assert!(matches!(err.kind(), Error::AllocationBudgetExceeded));
+    }
+
+    #[test]
+    #[should_panic(expected = "decode requested more than 1024 bytes")]
+    fn assert_alloc_within_catches_large_requests() {
+        let bytes = vec![0u64; 1024].encode_to_bytes().unwrap();
+        let ctx = Context::new(Endianness::Little);
+        assert_alloc_within::<Vec<u64>>(&bytes, ctx, 1024);
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    context::Epoch,
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+const WINDOWS_TO_UNIX_SECS: u64 = 11_644_473_600;
+const NANOS_PER_TICK: u32 = 100;
+const TICKS_PER_SEC: u64 = 10_000_000;
+
+fn windows_epoch() -> SystemTime {
+    UNIX_EPOCH - Duration::from_secs(WINDOWS_TO_UNIX_SECS)
+}
+
+// A `Duration` is written as whole seconds (`u64`) followed by the subsecond
+// nanoseconds (`u32`). `SystemTime` under `Epoch::Unix` uses the same layout
+// for its offset from `UNIX_EPOCH`.
+impl Encode for Duration {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        encoder.encode_u64(self.as_secs())?;
+        encoder.encode_u32(self.subsec_nanos())
+    }
+}
+
+impl Decode for Duration {
+    const MIN_SIZE: usize = 12;
+    const FIXED_SIZE: Option<usize> = Some(12);
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let secs = decoder.decode_u64()?;
+        let nanos = decoder.decode_u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err("Invalid nanoseconds".into());
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+impl Encode for SystemTime {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+        match encoder.context().epoch {
+            Epoch::Unix => self
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| "Time before epoch")?
+                .encode(encoder),
+            Epoch::Windows => {
+                let since = self
+                    .duration_since(windows_epoch())
+                    .map_err(|_| "Time before epoch")?;
+                let ticks = since
+                    .as_secs()
+                    .checked_mul(TICKS_PER_SEC)
+                    .and_then(|ticks| {
+                        ticks.checked_add((since.subsec_nanos() / NANOS_PER_TICK) as u64)
+                    })
+                    .ok_or("Timestamp out of range")?;
+                encoder.encode_u64(ticks)
+            }
+        }
+    }
+}
+
+impl Decode for SystemTime {
+    const MIN_SIZE: usize = 8;
+
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let (epoch, since) = match decoder.context().epoch {
+            Epoch::Unix => (UNIX_EPOCH, Duration::decode(decoder)?),
+            Epoch::Windows => {
+                let ticks = decoder.decode_u64()?;
+                let nanos = (ticks % TICKS_PER_SEC) as u32 * NANOS_PER_TICK;
+                (windows_epoch(), Duration::new(ticks / TICKS_PER_SEC, nanos))
+            }
+        };
+        epoch
+            .checked_add(since)
+            .ok_or_else(|| "Timestamp out of range".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::prelude::*;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Stamps {
+        #[bin(epoch = "unix")]
+        unix: SystemTime,
+        #[bin(epoch = "windows")]
+        windows: SystemTime,
+    }
+
+    // 2023-11-14T22:13:20.5Z
+    fn known() -> SystemTime {
+        UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000)
+    }
+
+    #[test]
+    fn epochs_map_known_timestamp() {
+        let stamps = Stamps {
+            unix: known(),
+            windows: known(),
+        };
+        let bytes = stamps.encode_to_bytes().unwrap();
+
+        let mut decoder = BufferDecoder::new(&bytes);
+        assert_eq!(decoder.decode_u64().unwrap(), 1_700_000_000);
+        assert_eq!(decoder.decode_u32().unwrap(), 500_000_000);
+        assert_eq!(decoder.decode_u64().unwrap(), 133_444_736_005_000_000);
+
+        assert_eq!(Stamps::decode_from_bytes(&bytes).unwrap(), stamps);
+    }
+
+    #[test]
+    fn windows_epoch_predates_unix_epoch() {
+        let stamps = Stamps {
+            unix: UNIX_EPOCH,
+            windows: UNIX_EPOCH - Duration::from_secs(86_400),
+        };
+        let bytes = stamps.encode_to_bytes().unwrap();
+        assert_eq!(Stamps::decode_from_bytes(&bytes).unwrap(), stamps);
+
+        let before = Stamps {
+            unix: UNIX_EPOCH - Duration::from_secs(1),
+            windows: UNIX_EPOCH,
+        };
+        assert!(before.encode_to_bytes().is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{decoder::Decoder, encoder::Encoder, error::Error};
+
+pub fn encode_varint_u64<E: Encoder + ?Sized>(
+    encoder: &mut E,
+    mut value: u64,
+) -> Result<(), Error> {
+    let mut bytes = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    encoder.encode_bytes(&bytes[..len])
+}
+
+pub fn decode_varint_u64<D: Decoder + ?Sized>(decoder: &mut D) -> Result<u64, Error> {
+    let mut value = 0u64;
+    for index in 0..10 {
+        let byte = decoder.decode_u8()?;
+        if index == 9 && byte > 1 {
+            return Err("Varint overflow".into());
+        }
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            if byte == 0 && index > 0 {
+                return Err("Overlong varint".into());
+            }
+            return Ok(value);
+        }
+    }
+    Err("Varint overflow".into())
+}
+
+pub fn encode_varint_i64<E: Encoder + ?Sized>(encoder: &mut E, value: i64) -> Result<(), Error> {
+    encode_varint_u64(encoder, ((value << 1) ^ (value >> 63)) as u64)
+}
+
+pub fn decode_varint_i64<D: Decoder + ?Sized>(decoder: &mut D) -> Result<i64, Error> {
+    let value = decode_varint_u64(decoder)?;
+    Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+}
+
+pub trait VarInt: Sized {
+    fn encode_varint<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error>;
+
+    fn decode_varint<D: Decoder>(decoder: &mut D) -> Result<Self, Error>;
+}
+
+macro_rules! impl_varint {
+    ($($ty:ty => $encode:ident, $decode:ident, $wide:ty);*) => {
+        $(
+            impl VarInt for $ty {
+                fn encode_varint<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+                    $encode(encoder, *self as $wide)
+                }
+
+                fn decode_varint<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+                    <$ty>::try_from($decode(decoder)?).map_err(|_| "Varint out of range".into())
+                }
+            }
+        )*
+    };
+}
+
+impl_varint!(
+    u8 => encode_varint_u64, decode_varint_u64, u64;
+    u16 => encode_varint_u64, decode_varint_u64, u64;
+    u32 => encode_varint_u64, decode_varint_u64, u64;
+    u64 => encode_varint_u64, decode_varint_u64, u64;
+    usize => encode_varint_u64, decode_varint_u64, u64;
+    i8 => encode_varint_i64, decode_varint_i64, i64;
+    i16 => encode_varint_i64, decode_varint_i64, i64;
+    i32 => encode_varint_i64, decode_varint_i64, i64;
+    i64 => encode_varint_i64, decode_varint_i64, i64
+);
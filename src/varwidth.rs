@@ -0,0 +1,164 @@
+use crate::{
+    decoder::{Decode, Decoder},
+    encoder::{Encode, Encoder},
+    error::Error,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VarWidth<T>(pub T);
+
+fn encode_unsigned<E: Encoder>(value: u128, encoder: &mut E) -> Result<(), Error> {
+    if let Ok(value) = u8::try_from(value) {
+        encoder.encode_u8(1)?;
+        encoder.encode_u8(value)
+    } else if let Ok(value) = u16::try_from(value) {
+        encoder.encode_u8(2)?;
+        encoder.encode_u16(value)
+    } else if let Ok(value) = u32::try_from(value) {
+        encoder.encode_u8(4)?;
+        encoder.encode_u32(value)
+    } else if let Ok(value) = u64::try_from(value) {
+        encoder.encode_u8(8)?;
+        encoder.encode_u64(value)
+    } else {
+        encoder.encode_u8(16)?;
+        encoder.encode_u128(value)
+    }
+}
+
+fn decode_unsigned<D: Decoder>(decoder: &mut D) -> Result<u128, Error> {
+    let start = decoder.position();
+    match decoder.decode_u8()? {
+        1 => Ok(decoder.decode_u8()?.into()),
+        2 => Ok(decoder.decode_u16()?.into()),
+        4 => Ok(decoder.decode_u32()?.into()),
+        8 => Ok(decoder.decode_u64()?.into()),
+        16 => decoder.decode_u128(),
+        _ => Err(Error::at(start, Error::InvalidTag)),
+    }
+}
+
+fn encode_signed<E: Encoder>(value: i128, encoder: &mut E) -> Result<(), Error> {
+    if let Ok(value) = i8::try_from(value) {
+        encoder.encode_u8(1)?;
+        encoder.encode_i8(value)
+    } else if let Ok(value) = i16::try_from(value) {
+        encoder.encode_u8(2)?;
+        encoder.encode_i16(value)
+    } else if let Ok(value) = i32::try_from(value) {
+        encoder.encode_u8(4)?;
+        encoder.encode_i32(value)
+    } else if let Ok(value) = i64::try_from(value) {
+        encoder.encode_u8(8)?;
+        encoder.encode_i64(value)
+    } else {
+        encoder.encode_u8(16)?;
+        encoder.encode_i128(value)
+    }
+}
+
+fn decode_signed<D: Decoder>(decoder: &mut D) -> Result<i128, Error> {
+    let start = decoder.position();
+    match decoder.decode_u8()? {
+        1 => Ok(decoder.decode_i8()?.into()),
+        2 => Ok(decoder.decode_i16()?.into()),
+        4 => Ok(decoder.decode_i32()?.into()),
+        8 => Ok(decoder.decode_i64()?.into()),
+        16 => decoder.decode_i128(),
+        _ => Err(Error::at(start, Error::InvalidTag)),
+    }
+}
+
+macro_rules! impl_var_width {
+    ($encode:ident, $decode:ident, $wide:ty => $($ty:ty), *) => {
+        $(
+            impl Encode for VarWidth<$ty> {
+                fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), Error> {
+                    $encode(<$wide>::from(self.0), encoder)
+                }
+            }
+
+            impl Decode for VarWidth<$ty> {
+                const MIN_SIZE: usize = 2;
+
+                fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+                    let value = $decode(decoder)?;
+                    Ok(Self(<$ty>::try_from(value).map_err(|_| "VarWidth value out of range")?))
+                }
+            }
+        )*
+    };
+}
+
+impl_var_width!(encode_unsigned, decode_unsigned, u128 => u8, u16, u32, u64, u128);
+impl_var_width!(encode_signed, decode_signed, i128 => i8, i16, i32, i64, i128);
+
+#[cfg(test)]
+mod tests {
+    use super::VarWidth;
+    use crate::prelude::*;
+
+    #[test]
+    fn unsigned_values_pick_the_narrowest_width() {
+        let cases: [(u128, usize); 10] = [
+            (0, 1),
+            (u8::MAX.into(), 1),
+            (u128::from(u8::MAX) + 1, 2),
+            (u16::MAX.into(), 2),
+            (u128::from(u16::MAX) + 1, 4),
+            (u32::MAX.into(), 4),
+            (u128::from(u32::MAX) + 1, 8),
+            (u64::MAX.into(), 8),
+            (u128::from(u64::MAX) + 1, 16),
+            (u128::MAX, 16),
+        ];
+        for (value, width) in cases {
+            let bytes = VarWidth(value).encode_to_bytes().unwrap();
+            assert_eq!(bytes.len(), 1 + width, "{value}");
+            assert_eq!(usize::from(bytes[0]), width);
+            assert_eq!(
+                VarWidth::<u128>::decode_from_bytes(&bytes).unwrap(),
+                VarWidth(value)
+            );
+        }
+    }
+
+    #[test]
+    fn signed_values_pick_the_narrowest_width() {
+        let cases: [(i128, usize); 14] = [
+            (0, 1),
+            (i8::MIN.into(), 1),
+            (i8::MAX.into(), 1),
+            (i128::from(i8::MIN) - 1, 2),
+            (i128::from(i8::MAX) + 1, 2),
+            (i16::MAX.into(), 2),
+            (i128::from(i16::MAX) + 1, 4),
+            (i32::MIN.into(), 4),
+            (i128::from(i32::MIN) - 1, 8),
+            (i64::MAX.into(), 8),
+            (i128::from(i64::MAX) + 1, 16),
+            (i128::from(i64::MIN) - 1, 16),
+            (i128::MIN, 16),
+            (i128::MAX, 16),
+        ];
+        for (value, width) in cases {
+            let bytes = VarWidth(value).encode_to_bytes().unwrap();
+            assert_eq!(bytes.len(), 1 + width, "{value}");
+            assert_eq!(usize::from(bytes[0]), width);
+            assert_eq!(
+                VarWidth::<i128>::decode_from_bytes(&bytes).unwrap(),
+                VarWidth(value)
+            );
+        }
+    }
+
+    #[test]
+    fn narrow_types_reject_values_wider_than_themselves() {
+        let bytes = VarWidth(300u32).encode_to_bytes().unwrap();
+        assert_eq!(
+            VarWidth::<u16>::decode_from_bytes(&bytes).unwrap(),
+            VarWidth(300)
+        );
+        assert!(VarWidth::<u8>::decode_from_bytes(&bytes).is_err());
+    }
+}